@@ -0,0 +1,62 @@
+#[macro_use]
+extern crate criterion;
+extern crate lcov_parser;
+
+use criterion:: { Criterion, Throughput };
+use lcov_parser:: { LCOVParser, LCOVRecord, LCOVRecordRef };
+
+fn build_report(files: usize) -> String {
+    let mut report = String::new();
+    for file in 0..files {
+        report.push_str("TN:bench\n");
+        report.push_str(&format!("SF:/path/to/source/directory/file{}.c\n", file));
+        for function in 0..10 {
+            report.push_str(&format!("FN:{},function_{}_{}\n", function * 10 + 1, file, function));
+            report.push_str(&format!("FNDA:{},function_{}_{}\n", function, file, function));
+        }
+        report.push_str("FNF:10\nFNH:9\n");
+        for line in 1..100 {
+            report.push_str(&format!("BRDA:{},0,0,{}\n", line, line % 3));
+            report.push_str(&format!("BRDA:{},0,1,-\n", line));
+        }
+        report.push_str("BRF:198\nBRH:66\n");
+        for line in 1..100 {
+            report.push_str(&format!("DA:{},{},PF4Rz2r7RTliO9u6bZ7h6g\n", line, line % 5));
+        }
+        report.push_str("LF:99\nLH:80\nend_of_record\n");
+    }
+    report
+}
+
+fn parse(c: &mut Criterion) {
+    let report = build_report(100);
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(report.len() as u64));
+
+    group.bench_function("next", |b| b.iter(|| {
+        let mut parser = LCOVParser::new(report.as_bytes());
+        let mut source_files = 0;
+        while let Some(record) = parser.next().unwrap() {
+            if let LCOVRecord::SourceFile(_) = record {
+                source_files += 1;
+            }
+        }
+        source_files
+    }));
+
+    group.bench_function("next_ref", |b| b.iter(|| {
+        let mut parser = LCOVParser::new(report.as_bytes());
+        let mut source_files = 0;
+        while let Some(record) = parser.next_ref().unwrap() {
+            if let LCOVRecordRef::SourceFile(_) = record {
+                source_files += 1;
+            }
+        }
+        source_files
+    }));
+
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -13,8 +13,8 @@ fn main() {
         Ok(report) => {
             let tmp_dir = TempDir::new("example_report").expect("create temp dir");
             let file_path = tmp_dir.path().join("merged_report.lcov");
-            report.save_as(file_path.as_path().clone())
+            report.save_as(file_path.as_path())
         },
-        Err(err) => panic!(err)
+        Err(err) => panic!("{}", err)
     };
 }
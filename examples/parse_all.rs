@@ -10,8 +10,8 @@ fn main() {
 
     for record in records.iter() {
         match record {
-            &LCOVRecord::SourceFile(ref file_name) => println!("File: {}", file_name),
-            &LCOVRecord::EndOfRecord => println!("Finish"),
+            LCOVRecord::SourceFile(file_name) => println!("File: {}", file_name),
+            LCOVRecord::EndOfRecord => println!("Finish"),
             _ => { continue; }
         }
     }
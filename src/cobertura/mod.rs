@@ -0,0 +1,77 @@
+// Copyright (c) 2015-2016 lcov-parser developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Conversion between the report and [Cobertura](http://cobertura.github.io/cobertura/) XML.
+//!
+//! The report is converted as follows.
+//!
+//! * package: directory of the source files
+//! * class: source file, the tests of the file are combined
+//! * method: function
+//! * line: line, with the condition coverage of the branches on the line
+
+mod writer;
+mod reader;
+
+pub use self::writer:: { CoberturaWriter };
+pub use self::reader:: { CoberturaReader, CoberturaError };
+
+use std::path:: { Path };
+use report::coverage:: { Coverage };
+use report::counter:: { HitCounter, FoundCounter };
+
+/// Package name of the source file, the directory separated by dots
+fn package_name(source_name: &str) -> String {
+    let directory = Path::new(source_name).parent()
+        .map(|directory| directory.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    directory.trim_start_matches('/').replace('/', ".")
+}
+
+/// Class name of the source file, the path without the extension separated by dots
+fn class_name(source_name: &str) -> String {
+    let path = Path::new(source_name);
+    let class_path = match (path.parent(), path.file_stem()) {
+        (Some(directory), Some(stem)) => directory.join(stem),
+        _ => path.to_path_buf()
+    };
+    class_path.to_string_lossy().trim_start_matches('/').replace('/', ".")
+}
+
+fn rate(coverage: &Coverage) -> String {
+    match coverage.percentage() {
+        Some(percentage) => format!("{}", (percentage * 100.0).round() / 10000.0),
+        None => "0".to_string()
+    }
+}
+
+fn condition_coverage(coverage: &Coverage) -> String {
+    let percentage = coverage.percentage().unwrap_or(0.0).round();
+    format!("{}% ({}/{})", percentage, coverage.hit_count(), coverage.found_count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(package_name("/path/to/source.c"), "path.to");
+        assert_eq!(package_name("source.c"), "");
+        assert_eq!(class_name("/path/to/source.c"), "path.to.source");
+        assert_eq!(class_name("source.c"), "source");
+    }
+
+    #[test]
+    fn rates() {
+        assert_eq!(rate(&Coverage::new(2, 3)), "0.6667");
+        assert_eq!(rate(&Coverage::new(1, 1)), "1");
+        assert_eq!(rate(&Coverage::new(0, 0)), "0");
+        assert_eq!(condition_coverage(&Coverage::new(1, 2)), "50% (1/2)");
+    }
+}
@@ -0,0 +1,370 @@
+// Copyright (c) 2015-2016 lcov-parser developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+extern crate quick_xml;
+
+use self::quick_xml::events:: { Event, BytesStart };
+use self::quick_xml::reader:: { Reader };
+use std::io:: { BufRead, BufReader, Error as IOError };
+use std::path:: { Path };
+use std::str:: { FromStr };
+use std::error::Error;
+use std::fmt;
+use record:: { LineData, FunctionName, FunctionData, BranchData };
+use merger::ops:: { TryMerge, TestError, ChecksumError, FunctionError, BranchError };
+use report:: { Report };
+use report::test:: { Tests };
+use report::file:: { File, Files };
+use report::attribute:: { TestName, SourceFile, LineNumber, ExecutionCount };
+use compression:: { TracefileReader };
+
+#[derive(Debug)]
+pub enum CoberturaError {
+    IO(IOError),
+    /// Malformed XML, with the byte position of the error
+    Xml(u64, String),
+    MissingAttribute(String, String),
+    InvalidAttribute(String, String, String),
+    Merge(TestError)
+}
+
+impl_from_error!(IOError, CoberturaError::IO);
+impl_from_error!(TestError, CoberturaError::Merge);
+impl_from_error!(ChecksumError, TestError::Checksum=>CoberturaError::Merge);
+impl_from_error!(FunctionError, TestError::Function=>CoberturaError::Merge);
+impl_from_error!(BranchError, TestError::Branch=>CoberturaError::Merge);
+
+impl fmt::Display for CoberturaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CoberturaError::IO(err) => write!(f, "{}", err),
+            CoberturaError::Xml(position, message) => write!(f, "{} at position {}", message, position),
+            CoberturaError::MissingAttribute(element, name) => write!(f, "<{}> has no {} attribute", element, name),
+            CoberturaError::InvalidAttribute(element, name, value) => write!(f, "<{}> has invalid {} attribute: {}", element, name, value),
+            CoberturaError::Merge(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl Error for CoberturaError {
+}
+
+struct Method {
+    name: String,
+    lines: Vec<(LineNumber, ExecutionCount)>
+}
+
+struct Class {
+    source_name: SourceFile,
+    tests: Tests
+}
+
+/// Read Cobertura XML into the report, which can be merged with the LCOV reports.
+///
+/// The relative file names of the classes are joined to the root of `<sources>` if it has a single `<source>`.
+/// With several roots, the root of each file is unknown and the relative file names are kept,
+/// so `PathRule` is needed to map them to the source files of the LCOV reports.
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser:: { CoberturaReader, ReportMerger };
+///
+/// let report = CoberturaReader::open("tests/fixtures/cobertura/coverage.xml").unwrap().read().unwrap();
+///
+/// let mut merger = ReportMerger::new();
+/// merger.merge_report(&report).unwrap();
+/// merger.merge_file("tests/fixtures/merged/eq_checksum/fixture2.info").unwrap();
+///
+/// assert_eq!(merger.report().len(), 5);
+/// ```
+pub struct CoberturaReader<R: BufRead> {
+    reader: Reader<R>,
+    test_name: TestName
+}
+
+impl CoberturaReader<BufReader<TracefileReader>> {
+    /// Open the file, the compressed file is decoded as `LCOVParser::open`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CoberturaError> {
+        let file = TracefileReader::open(path)?;
+        Ok(CoberturaReader::new(BufReader::new(file)))
+    }
+}
+
+impl<R: BufRead> CoberturaReader<R> {
+    pub fn new(reader: R) -> Self {
+        let mut reader = Reader::from_reader(reader);
+        reader.config_mut().trim_text(true);
+
+        CoberturaReader {
+            reader,
+            test_name: TestName::new()
+        }
+    }
+    /// Set the test name of the records, the name is empty by default
+    pub fn set_test_name<S: Into<TestName>>(&mut self, test_name: S) {
+        self.test_name = test_name.into();
+    }
+    pub fn read(mut self) -> Result<Report, CoberturaError> {
+        let mut files = Files::new();
+        let mut class: Option<Class> = None;
+        let mut method: Option<Method> = None;
+        let mut sources: Vec<String> = vec![];
+        let mut in_source = false;
+        let mut buffer = vec![];
+
+        loop {
+            let event = match self.reader.read_event_into(&mut buffer) {
+                Ok(event) => event,
+                Err(err) => return Err(CoberturaError::Xml(self.reader.buffer_position(), err.to_string()))
+            };
+            match event {
+                Event::Start(ref element) | Event::Empty(ref element) => {
+                    let is_empty = matches!(event, Event::Empty(_));
+
+                    match element.local_name().as_ref() {
+                        b"source" => in_source = !is_empty,
+                        b"class" => {
+                            let source_name = source_path(&sources, required(element, "filename")?);
+                            class = Some(Class { source_name, tests: Tests::new() });
+                        },
+                        b"method" if !is_empty => {
+                            let name: String = required(element, "name")?;
+                            let signature = attribute(element, "signature")?.unwrap_or_default();
+                            method = Some(Method { name: name + &signature, lines: vec![] });
+                        },
+                        b"line" => {
+                            let line_number = required(element, "number")?;
+                            let hits = required(element, "hits")?;
+
+                            if let Some(ref mut method) = method {
+                                method.lines.push((line_number, hits));
+                            } else if let Some(ref mut class) = class {
+                                self.on_line(element, &mut class.tests, line_number, hits)?;
+                            }
+                        },
+                        _ => {}
+                    }
+                    if is_empty && element.local_name().as_ref() == b"class" {
+                        let class = class.take().unwrap();
+                        files.try_merge((&class.source_name, &File::new(class.tests)))?;
+                    }
+                },
+                Event::Text(ref text) if in_source => {
+                    let source = text.unescape()
+                        .map_err(|err| CoberturaError::Xml(self.reader.buffer_position(), err.to_string()))?;
+                    sources.push(source.into_owned());
+                },
+                Event::End(ref element) => {
+                    match element.local_name().as_ref() {
+                        b"source" => in_source = false,
+                        b"method" => {
+                            if let (Some(method), Some(ref mut class)) = (method.take(), class.as_mut()) {
+                                self.on_method(method, &mut class.tests)?;
+                            }
+                        },
+                        b"class" => {
+                            if let Some(class) = class.take() {
+                                files.try_merge((&class.source_name, &File::new(class.tests)))?;
+                            }
+                        },
+                        _ => {}
+                    }
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+            buffer.clear();
+        }
+        Ok(Report::new(files))
+    }
+    fn on_line(&self, element: &BytesStart, tests: &mut Tests, line_number: LineNumber, hits: ExecutionCount) -> Result<(), CoberturaError> {
+        let line_data = LineData { line: line_number, count: hits, checksum: None };
+        tests.try_merge((&self.test_name, &line_data))?;
+
+        let is_branch = attribute(element, "branch")?.map(|branch| branch == "true").unwrap_or(false);
+        if !is_branch {
+            return Ok(());
+        }
+        let condition_coverage = match attribute(element, "condition-coverage")? {
+            Some(condition_coverage) => condition_coverage,
+            None => return Ok(())
+        };
+        let (covered, total) = match parse_condition_coverage(&condition_coverage) {
+            Some(conditions) => conditions,
+            None => return Err(CoberturaError::InvalidAttribute("line".to_string(), "condition-coverage".to_string(), condition_coverage))
+        };
+
+        for branch in 0..total {
+            let taken = if hits == 0 {
+                None
+            } else if branch < covered {
+                Some(1)
+            } else {
+                Some(0)
+            };
+            let branch_data = BranchData { line: line_number, block: 0, branch, taken };
+            tests.try_merge((&self.test_name, &branch_data))?;
+        }
+        Ok(())
+    }
+    fn on_method(&self, method: Method, tests: &mut Tests) -> Result<(), CoberturaError> {
+        let (line, count) = match method.lines.first() {
+            Some(&(line, count)) => (line, count),
+            None => return Ok(())
+        };
+        let end = method.lines.iter().map(|&(line_number, _)| line_number).max().filter(|end| *end > line);
+
+        let function_name = FunctionName { name: method.name.clone(), line, end };
+        let function_data = FunctionData { name: method.name, count };
+        tests.try_merge((&self.test_name, &function_name))?;
+        tests.try_merge((&self.test_name, &function_data))?;
+        Ok(())
+    }
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Result<Option<String>, CoberturaError> {
+    let element_name = || String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+    let attribute = element.try_get_attribute(name)
+        .map_err(|err| CoberturaError::InvalidAttribute(element_name(), name.to_string(), err.to_string()))?;
+
+    match attribute {
+        Some(attribute) => {
+            let value = attribute.unescape_value()
+                .map_err(|err| CoberturaError::InvalidAttribute(element_name(), name.to_string(), err.to_string()))?;
+            Ok(Some(value.into_owned()))
+        },
+        None => Ok(None)
+    }
+}
+
+fn required<T: FromStr>(element: &BytesStart, name: &str) -> Result<T, CoberturaError> {
+    let element_name = || String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+    let value = match attribute(element, name)? {
+        Some(value) => value,
+        None => return Err(CoberturaError::MissingAttribute(element_name(), name.to_string()))
+    };
+    value.parse::<T>().map_err(|_| CoberturaError::InvalidAttribute(element_name(), name.to_string(), value))
+}
+
+/// Returns the file name joined to the root of the sources, if the root is the only one
+fn source_path(sources: &[String], filename: String) -> SourceFile {
+    match sources {
+        [root] => Path::new(root).join(&filename).to_string_lossy().into_owned(),
+        _ => filename
+    }
+}
+
+/// Parse the covered and total conditions of `50% (1/2)`
+fn parse_condition_coverage(value: &str) -> Option<(u32, u32)> {
+    let start = value.find('(')?;
+    let end = value.find(')')?;
+    let mut conditions = value.get(start + 1..end)?.split('/');
+    let covered = conditions.next()?.trim().parse().ok()?;
+    let total = conditions.next()?.trim().parse().ok()?;
+    Some((covered, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use cobertura::reader:: { CoberturaReader, CoberturaError, parse_condition_coverage };
+    use merger:: { merge_files, ReportMerger };
+    use report::summary:: { Summary };
+    use report::test:: { Test };
+    use report::attribute:: { LineNumber, ExecutionCount };
+
+    #[test]
+    fn condition_coverage() {
+        assert_eq!(parse_condition_coverage("50% (1/2)"), Some((1, 2)));
+        assert_eq!(parse_condition_coverage("100%"), None);
+    }
+
+    #[test]
+    fn read_written_report() {
+        let expected = merge_files(&[
+            "tests/fixtures/merged/eq_checksum/fixture1.info",
+            "tests/fixtures/merged/not_executed_branch/fixture.info"
+        ]).unwrap().flatten_tests().unwrap();
+
+        let report = CoberturaReader::open("tests/fixtures/cobertura/coverage.xml").unwrap().read().unwrap();
+        assert_eq!(report.len(), expected.len());
+
+        for (source_name, file) in expected.files().iter() {
+            let expected_test = file.combined().unwrap();
+            let test = report.get(source_name).unwrap().get_test(&String::new()).unwrap();
+
+            let execution_counts = | test: &Test | -> Vec<(LineNumber, ExecutionCount)> {
+                test.lines().iter().map(|(line_number, line)| (*line_number, *line.execution_count())).collect()
+            };
+            assert_eq!(execution_counts(test), execution_counts(&expected_test));
+            assert_eq!(test.functions().to_string(), expected_test.functions().to_string());
+            assert_eq!(test.coverage_summary().branches, expected_test.coverage_summary().branches);
+        }
+    }
+
+    #[test]
+    fn read_java_report() {
+        let mut reader = CoberturaReader::open("tests/fixtures/cobertura/java.xml").unwrap();
+        reader.set_test_name("java");
+        let report = reader.read().unwrap();
+
+        let file = report.get("/home/user/project/src/main/java/com/example/Greeter.java").unwrap();
+        let test = file.get_test(&"java".to_string()).unwrap();
+
+        assert_eq!(test.lines().len(), 6);
+        assert_eq!(test.lines().get(&12).unwrap().execution_count(), &3);
+
+        let function = test.functions().get(&"greet(Ljava/lang/String;)Ljava/lang/String;".to_string()).unwrap();
+        assert_eq!(function.line_number(), &11);
+        assert_eq!(function.end_line_number(), Some(&14));
+        assert_eq!(function.execution_count(), &3);
+
+        let branches = test.branches().get(&12).unwrap();
+        assert_eq!(branches.len(), 2);
+        assert_eq!(test.branches().get(&14), None);
+        assert_eq!(report.to_string().lines().filter(|line| line.starts_with("BRDA:20,")).count(), 2);
+    }
+
+    #[test]
+    fn merge_with_lcov_report() {
+        let report = CoberturaReader::open("tests/fixtures/cobertura/relative.xml").unwrap().read().unwrap();
+        assert!(report.get("/src/fixture1.c").is_some());
+
+        let mut merger = ReportMerger::new();
+        merger.merge_report(&report).unwrap();
+        merger.merge_reader("TN:\nSF:/src/fixture1.c\nDA:4,2\nDA:6,0\nend_of_record\n".as_bytes()).unwrap();
+
+        let merged = merger.report();
+        assert_eq!(merged.len(), 1);
+
+        let test = merged.get("/src/fixture1.c").unwrap().get_test(&String::new()).unwrap();
+        assert_eq!(test.lines().get(&4).unwrap().execution_count(), &3);
+        assert_eq!(test.lines().get(&6).unwrap().execution_count(), &1);
+    }
+
+    #[test]
+    fn read_several_sources() {
+        let xml = concat!(
+            "<coverage><sources><source>/src</source><source>/lib</source></sources>",
+            "<packages><package><classes><class name=\"a\" filename=\"a.c\"/></classes></package></packages></coverage>"
+        );
+        let report = CoberturaReader::new(xml.as_bytes()).read().unwrap();
+        assert!(report.get("a.c").is_some());
+    }
+
+    #[test]
+    fn missing_attribute() {
+        let reader = CoberturaReader::new("<coverage><packages><package><classes><class name=\"a\"/></classes></package></packages></coverage>".as_bytes());
+        let result = reader.read();
+
+        assert!(match result {
+            Err(CoberturaError::MissingAttribute(element, name)) => element == "class" && name == "filename",
+            _ => false
+        });
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright (c) 2015-2016 lcov-parser developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::io:: { Write, Result, Error, ErrorKind };
+use std::collections::btree_map:: { BTreeMap };
+use std::time:: { SystemTime, UNIX_EPOCH };
+use report:: { Report };
+use report::test:: { Test };
+use report::summary:: { Summary };
+use report::counter:: { HitCounter, FoundCounter };
+use report::coverage:: { Coverage };
+use cobertura:: { package_name, class_name, rate, condition_coverage };
+use escape:: { escape };
+
+const DOCTYPE: &str = "<!DOCTYPE coverage SYSTEM \"http://cobertura.sourceforge.net/xml/coverage-04.dtd\">";
+
+/// Write the report as Cobertura XML
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser:: { CoberturaWriter, merge_files };
+///
+/// let report = merge_files(&[ "tests/fixtures/merged/eq_checksum/fixture1.info" ]).unwrap();
+///
+/// let mut writer = CoberturaWriter::new(vec![]);
+/// writer.set_timestamp(0);
+/// writer.write(&report).unwrap();
+///
+/// let output = String::from_utf8(writer.into_inner()).unwrap();
+/// assert!(output.contains("<class name=\"fixture1\" filename=\"/fixture1.c\""));
+/// ```
+pub struct CoberturaWriter<W: Write> {
+    output: W,
+    timestamp: u64,
+    sources: Vec<String>
+}
+
+impl<W: Write> CoberturaWriter<W> {
+    pub fn new(output: W) -> Self {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0);
+
+        CoberturaWriter {
+            output,
+            timestamp,
+            sources: vec![]
+        }
+    }
+    /// Set the timestamp in milliseconds, the current time is used by default
+    pub fn set_timestamp(&mut self, timestamp: u64) {
+        self.timestamp = timestamp;
+    }
+    /// Add the directory that the relative source files are based on
+    pub fn add_source<S: Into<String>>(&mut self, source: S) {
+        self.sources.push(source.into());
+    }
+    pub fn write(&mut self, report: &Report) -> Result<()> {
+        let mut packages: BTreeMap<String, Vec<(&String, Test)>> = BTreeMap::new();
+        let mut lines = Coverage::default();
+        let mut branches = Coverage::default();
+
+        for (source_name, file) in report.files().iter() {
+            let test = file.combined().map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+            let summary = test.coverage_summary();
+            lines += summary.lines;
+            branches += summary.branches;
+            packages.entry(package_name(source_name)).or_default().push((source_name, test));
+        }
+
+        writeln!(self.output, "<?xml version=\"1.0\" ?>")?;
+        writeln!(self.output, "{}", DOCTYPE)?;
+        writeln!(self.output, "<coverage line-rate=\"{}\" branch-rate=\"{}\" lines-covered=\"{}\" lines-valid=\"{}\" branches-covered=\"{}\" branches-valid=\"{}\" complexity=\"0\" version=\"1.9\" timestamp=\"{}\">",
+            rate(&lines), rate(&branches),
+            lines.hit_count(), lines.found_count(),
+            branches.hit_count(), branches.found_count(),
+            self.timestamp)?;
+
+        if !self.sources.is_empty() {
+            writeln!(self.output, "  <sources>")?;
+            for source in self.sources.iter() {
+                writeln!(self.output, "    <source>{}</source>", escape(source))?;
+            }
+            writeln!(self.output, "  </sources>")?;
+        }
+
+        writeln!(self.output, "  <packages>")?;
+        for (package_name, classes) in packages.iter() {
+            self.write_package(package_name, classes)?;
+        }
+        writeln!(self.output, "  </packages>")?;
+        writeln!(self.output, "</coverage>")
+    }
+    pub fn flush(&mut self) -> Result<()> {
+        self.output.flush()
+    }
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+    fn write_package(&mut self, package_name: &str, classes: &[(&String, Test)]) -> Result<()> {
+        let mut lines = Coverage::default();
+        let mut branches = Coverage::default();
+
+        for (_, test) in classes.iter() {
+            let summary = test.coverage_summary();
+            lines += summary.lines;
+            branches += summary.branches;
+        }
+
+        writeln!(self.output, "    <package name=\"{}\" line-rate=\"{}\" branch-rate=\"{}\" complexity=\"0\">",
+            escape(package_name), rate(&lines), rate(&branches))?;
+        writeln!(self.output, "      <classes>")?;
+        for (source_name, test) in classes.iter() {
+            self.write_class(source_name, test)?;
+        }
+        writeln!(self.output, "      </classes>")?;
+        writeln!(self.output, "    </package>")
+    }
+    fn write_class(&mut self, source_name: &str, test: &Test) -> Result<()> {
+        let summary = test.coverage_summary();
+
+        writeln!(self.output, "        <class name=\"{}\" filename=\"{}\" line-rate=\"{}\" branch-rate=\"{}\" complexity=\"0\">",
+            escape(&class_name(source_name)), escape(source_name), rate(&summary.lines), rate(&summary.branches))?;
+
+        writeln!(self.output, "          <methods>")?;
+        for (name, function) in test.functions().iter() {
+            let lines = match test.function_lines(name) {
+                Some(lines) => Coverage::from(&lines),
+                None => Coverage::new(function.is_hit() as usize, 1)
+            };
+            let branches = test.function_branches(name)
+                .map(|branches| Coverage::from(&branches))
+                .unwrap_or_default();
+
+            writeln!(self.output, "            <method name=\"{}\" signature=\"\" line-rate=\"{}\" branch-rate=\"{}\" complexity=\"0\">",
+                escape(name), rate(&lines), rate(&branches))?;
+            writeln!(self.output, "              <lines>")?;
+            writeln!(self.output, "                <line number=\"{}\" hits=\"{}\"/>", function.line_number(), function.execution_count())?;
+            writeln!(self.output, "              </lines>")?;
+            writeln!(self.output, "            </method>")?;
+        }
+        writeln!(self.output, "          </methods>")?;
+
+        writeln!(self.output, "          <lines>")?;
+        for (line_number, line) in test.lines().iter() {
+            match test.branches().get(line_number) {
+                Some(blocks) => {
+                    writeln!(self.output, "            <line number=\"{}\" hits=\"{}\" branch=\"true\" condition-coverage=\"{}\"/>",
+                        line_number, line.execution_count(), condition_coverage(&Coverage::from(blocks)))?;
+                },
+                None => {
+                    writeln!(self.output, "            <line number=\"{}\" hits=\"{}\"/>", line_number, line.execution_count())?;
+                }
+            }
+        }
+        writeln!(self.output, "          </lines>")?;
+        writeln!(self.output, "        </class>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tempdir;
+
+    use self::tempdir::TempDir;
+    use std::fs;
+    use std::io::Read;
+    use std::process:: { Command };
+    use merger:: { merge_files };
+    use cobertura::writer:: { CoberturaWriter };
+
+    fn write_fixture() -> String {
+        let report = merge_files(&[
+            "tests/fixtures/merged/eq_checksum/fixture1.info",
+            "tests/fixtures/merged/not_executed_branch/fixture.info"
+        ]).unwrap();
+
+        let mut writer = CoberturaWriter::new(vec![]);
+        writer.set_timestamp(1500000000000);
+        writer.add_source("/src");
+        writer.write(&report).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn write_report() {
+        let expected = {
+            let mut output = String::new();
+            let mut f = fs::File::open("tests/fixtures/cobertura/coverage.xml").unwrap();
+            let _ = f.read_to_string(&mut output);
+            output
+        };
+        assert_eq!(write_fixture(), expected);
+    }
+
+    /// Requires xmllint, run with `cargo test --features cobertura -- --ignored validate_with_dtd`
+    #[test]
+    #[ignore]
+    fn validate_with_dtd() {
+        let tmp_dir = TempDir::new("cobertura").expect("create temp dir");
+        let file_path = tmp_dir.path().join("coverage.xml");
+        fs::write(&file_path, write_fixture()).unwrap();
+
+        let output = Command::new("xmllint")
+            .arg("--noout")
+            .arg("--dtdvalid").arg("tests/fixtures/cobertura/coverage-04.dtd")
+            .arg(&file_path)
+            .output()
+            .expect("run xmllint");
+
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+}
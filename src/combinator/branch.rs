@@ -6,27 +6,27 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
-use combine:: { token, value, try, between, parser, Parser, ParseResult, State, Stream };
-use combine::char:: { string, newline };
-use record:: { LCOVRecord, BranchData };
-use combinator::value:: { to_integer };
+use combine:: { token, value, try, between, parser, Parser, ParseResult, State };
+use combine::char:: { string };
+use record:: { LCOVRecordRef, BranchData };
+use combinator::value:: { to_integer, to_count, end_of_line };
 
 #[inline]
-pub fn branch_record<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    try(parser(branch_data::<I>))
-        .or(try(parser(branches_found::<I>)))
-        .or(parser(branches_hit::<I>))
+pub fn branch_record<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    try(parser(branch_data))
+        .or(try(parser(branches_found)))
+        .or(parser(branches_hit))
         .parse_stream(input)
 }
 
 #[inline]
-fn branch_data<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    let line_number = parser(to_integer::<I>);
-    let block_number = token(',').with( parser(to_integer::<I>) );
-    let branch_number = token(',').with( parser(to_integer::<I>) );
+fn branch_data<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let line_number = parser(to_integer);
+    let block_number = token(',').with( parser(to_integer) );
+    let branch_number = token(',').with( parser(to_integer) );
 
-    let called = parser(to_integer::<I>);
-    let not_called = token('-').with( value(0) );
+    let called = parser(to_count).map( Some );
+    let not_called = token('-').with( value(None) );
 
     let branch_execution_count = try(not_called).or(called);
 
@@ -38,25 +38,25 @@ fn branch_data<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I:
             line: line_number,
             block: block_number,
             branch: branch_number,
-            taken: taken
+            taken
         };
-        LCOVRecord::from(branch)
+        LCOVRecordRef::from(branch)
     });
-    between(string("BRDA:"), newline(), record).parse_stream(input)
+    between(string("BRDA:"), parser(end_of_line), record).parse_stream(input)
 }
 
 #[inline]
-fn branches_found<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    let branches_found = parser(to_integer::<I>)
-        .map( | branches_found | LCOVRecord::BranchesFound(branches_found) );
+fn branches_found<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let branches_found = parser(to_integer)
+        .map( LCOVRecordRef::BranchesFound );
 
-    between(string("BRF:"), newline(), branches_found).parse_stream(input)
+    between(string("BRF:"), parser(end_of_line), branches_found).parse_stream(input)
 }
 
 #[inline]
-fn branches_hit<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    let branches_hit = parser(to_integer::<I>)
-        .map( | branches_hit | LCOVRecord::BranchesHit(branches_hit) );
+fn branches_hit<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let branches_hit = parser(to_integer)
+        .map( LCOVRecordRef::BranchesHit );
 
-    between(string("BRH:"), newline(), branches_hit).parse_stream(input)
+    between(string("BRH:"), parser(end_of_line), branches_hit).parse_stream(input)
 }
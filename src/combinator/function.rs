@@ -6,57 +6,90 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
-use combine:: { try, token, between, parser, Parser, ParseResult, State, Stream };
-use combine::char:: { string, newline };
-use record:: { LCOVRecord, FunctionName, FunctionData };
-use combinator::value:: { to_integer, to_string };
+use combine:: { try, token, optional, look_ahead, between, parser, Parser, ParseResult, State };
+use combine::char:: { string };
+use record:: { LCOVRecordRef, FunctionNameRef, FunctionDataRef, FunctionLine, FunctionAliasRef };
+use combinator::value:: { to_integer, to_count, to_str, end_of_line };
 
 #[inline]
-pub fn function_record<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    try(parser(function_name::<I>))
-        .or(try(parser(function_data::<I>)))
-        .or(try(parser(functions_found::<I>)))
-        .or(parser(functions_hit::<I>))
+pub fn function_record<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    try(parser(function_name))
+        .or(try(parser(function_data)))
+        .or(try(parser(function_line)))
+        .or(try(parser(function_alias)))
+        .or(try(parser(functions_found)))
+        .or(parser(functions_hit))
         .parse_stream(input)
 }
 
 #[inline]
-fn function_name<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    let line_number = parser(to_integer::<I>);
-    let function_name = token(',').with( parser(to_string::<I>) );
-
-    let record = (line_number, function_name).map( | t | {
-        let (line_number, function_name) = t;
-        let func = FunctionName { name: function_name, line: line_number };
-        LCOVRecord::from(func)
+fn function_name<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let line_number = parser(to_integer);
+    let end_line_number = optional( try(
+        token(',').with( parser(to_integer) ).skip( look_ahead(token(',')) )
+    ));
+    let function_name = token(',').with( parser(to_str) );
+
+    let record = (line_number, end_line_number, function_name).map( | t | {
+        let (line_number, end_line_number, function_name) = t;
+        let func = FunctionNameRef { name: function_name, line: line_number, end: end_line_number };
+        LCOVRecordRef::from(func)
     });
-    between(string("FN:"), newline(), record).parse_stream(input)
+    between(string("FN:"), parser(end_of_line), record).parse_stream(input)
 }
 
 #[inline]
-fn function_data<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    let execution_count = parser(to_integer::<I>);
+fn function_data<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let execution_count = parser(to_count);
     let function_name = token(',')
-        .with( parser(to_string::<I>) );
+        .with( parser(to_str) );
 
     let record = (execution_count, function_name).map( | t | {
         let (execution_count, function_name) = t;
-        let func_data = FunctionData { name: function_name, count: execution_count };
-        LCOVRecord::from(func_data)
+        let func_data = FunctionDataRef { name: function_name, count: execution_count };
+        LCOVRecordRef::from(func_data)
+    });
+    between(string("FNDA:"), parser(end_of_line), record).parse_stream(input)
+}
+
+#[inline]
+fn function_line<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let index = parser(to_integer);
+    let start_line_number = token(',').with( parser(to_integer) );
+    let end_line_number = optional( token(',').with( parser(to_integer) ) );
+
+    let record = (index, start_line_number, end_line_number).map( | t | {
+        let (index, start_line_number, end_line_number) = t;
+        let func_line = FunctionLine { index, start: start_line_number, end: end_line_number };
+        LCOVRecordRef::from(func_line)
+    });
+    between(string("FNL:"), parser(end_of_line), record).parse_stream(input)
+}
+
+#[inline]
+fn function_alias<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let index = parser(to_integer);
+    let execution_count = token(',').with( parser(to_count) );
+    let function_name = token(',').with( parser(to_str) );
+
+    let record = (index, execution_count, function_name).map( | t | {
+        let (index, execution_count, function_name) = t;
+        let func_alias = FunctionAliasRef { index, count: execution_count, name: function_name };
+        LCOVRecordRef::from(func_alias)
     });
-    between(string("FNDA:"), newline(), record).parse_stream(input)
+    between(string("FNA:"), parser(end_of_line), record).parse_stream(input)
 }
 
 #[inline]
-fn functions_found<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    let functions_found = parser(to_integer::<I>)
-        .map( | functions_found | LCOVRecord::FunctionsFound(functions_found) );
-    between(string("FNF:"), newline(), functions_found).parse_stream(input)
+fn functions_found<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let functions_found = parser(to_integer)
+        .map( LCOVRecordRef::FunctionsFound );
+    between(string("FNF:"), parser(end_of_line), functions_found).parse_stream(input)
 }
 
 #[inline]
-fn functions_hit<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    let functions_hit = parser(to_integer::<I>)
-        .map( | functions_hit | LCOVRecord::FunctionsHit(functions_hit) );
-    between(string("FNH:"), newline(), functions_hit).parse_stream(input)
+fn functions_hit<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let functions_hit = parser(to_integer)
+        .map( LCOVRecordRef::FunctionsHit );
+    between(string("FNH:"), parser(end_of_line), functions_hit).parse_stream(input)
 }
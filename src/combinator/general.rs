@@ -6,52 +6,51 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
-use combine:: { optional, token, value, between, parser, Parser, ParseResult, State, Stream };
-use combine::char:: { string, newline };
-use std::string:: { String };
-use record:: { LCOVRecord, LineData  };
-use combinator::value:: { to_integer, to_string };
+use combine:: { optional, token, value, between, parser, Parser, ParseResult, State };
+use combine::char:: { string };
+use record:: { LCOVRecordRef, LineDataRef };
+use combinator::value:: { to_integer, to_count, to_str, end_of_line };
 
 #[inline]
-pub fn general_record<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    parser(end_of_record::<I>)
-        .or(parser(test_name::<I>))
-        .or(parser(source_file::<I>))
-        .or(parser(data::<I>))
+pub fn general_record<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    parser(data)
+        .or(parser(end_of_record))
+        .or(parser(test_name))
+        .or(parser(source_file))
         .parse_stream(input)
 }
 
 #[inline]
-fn test_name<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    let test_name = optional(parser(to_string::<I>))
-        .map(| s: Option<String> | LCOVRecord::TestName(s));
-    between(string("TN:"), newline(), test_name).parse_stream(input)
+fn test_name<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let test_name = optional(parser(to_str))
+        .map( LCOVRecordRef::TestName );
+    between(string("TN:"), parser(end_of_line), test_name).parse_stream(input)
 }
 
 #[inline]
-fn source_file<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    let source_file =  parser(to_string::<I>).map( | s: String | LCOVRecord::SourceFile(s) );
-    between(string("SF:"), newline(), source_file).parse_stream(input)
+fn source_file<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let source_file = parser(to_str).map( LCOVRecordRef::SourceFile );
+    between(string("SF:"), parser(end_of_line), source_file).parse_stream(input)
 }
 
 #[inline]
-fn data<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    let line_number = parser(to_integer::<I>);
-    let execution_count = token(',').with( parser(to_integer::<I>) );
-    let checksum = optional( token(',').with( parser(to_string::<I>) ) );
+fn data<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let line_number = parser(to_integer);
+    let execution_count = token(',').with( parser(to_count) );
+    let checksum = optional( token(',').with( parser(to_str) ) );
     let record = (line_number, execution_count, checksum).map( | t | {
         let (line_number, execution_count, checksum) = t;
-        let line = LineData {
+        let line = LineDataRef {
             line: line_number,
             count: execution_count,
-            checksum: checksum
+            checksum
         };
-        LCOVRecord::from(line)
+        LCOVRecordRef::from(line)
     });
-    between(string("DA:"), newline(), record).parse_stream(input)
+    between(string("DA:"), parser(end_of_line), record).parse_stream(input)
 }
 
 #[inline]
-fn end_of_record<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    between(string("end_of_record"), newline(), value(LCOVRecord::EndOfRecord)).parse_stream(input)
+fn end_of_record<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    between(string("end_of_record"), parser(end_of_line), value(LCOVRecordRef::EndOfRecord)).parse_stream(input)
 }
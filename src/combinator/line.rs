@@ -6,30 +6,30 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
-use combine:: { try, between, parser, Parser, ParseResult, State, Stream };
-use combine::char:: { string, newline };
-use record:: { LCOVRecord };
-use combinator::value:: { to_integer };
+use combine:: { try, between, parser, Parser, ParseResult, State };
+use combine::char:: { string };
+use record:: { LCOVRecordRef };
+use combinator::value:: { to_integer, end_of_line };
 
 #[inline]
-pub fn lines_record<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    try(parser(lines_hit::<I>))
-        .or(parser(lines_found::<I>))
+pub fn lines_record<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    try(parser(lines_hit))
+        .or(parser(lines_found))
         .parse_stream(input)
 }
 
 #[inline]
-fn lines_hit<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    let line_count = parser(to_integer::<I>)
-        .map( | lines_hit | LCOVRecord::LinesHit(lines_hit) );
+fn lines_hit<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let line_count = parser(to_integer)
+        .map( LCOVRecordRef::LinesHit );
 
-    between(string("LH:"), newline(), line_count).parse_stream(input)
+    between(string("LH:"), parser(end_of_line), line_count).parse_stream(input)
 }
 
 #[inline]
-fn lines_found<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    let line_found = parser(to_integer::<I>)
-        .map( | line_found | LCOVRecord::LinesFound(line_found) );
+fn lines_found<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    let line_found = parser(to_integer)
+        .map( LCOVRecordRef::LinesFound );
 
-    between(string("LF:"), newline(), line_found).parse_stream(input)
+    between(string("LF:"), parser(end_of_line), line_found).parse_stream(input)
 }
@@ -13,8 +13,10 @@
 //!
 //! * TN:<test name>
 //! * SF:<absolute path to the source file>
-//! * FN:<line number of function start>,<function name> for each function
+//! * FN:<line number of function start>[,<line number of function end>],<function name> for each function
 //! * FNDA:<execution count>,<function name>
+//! * FNL:<index>,<line number of function start>[,<line number of function end>]
+//! * FNA:<index>,<execution count>,<function name>
 //! * FNF:<number of functions found>
 //! * FNH:<number of function hit>
 //! * DA:<line number>,<execution count>[,<checksum>]
@@ -24,8 +26,8 @@
 //! * BRH:<number of branches hit>
 //! * end_of_record
 
-use combine:: { parser, many, Parser, ParseResult, State, Stream };
-use record:: { LCOVRecord  };
+use combine:: { parser, many, Parser, ParseResult, State };
+use record:: { LCOVRecord, LCOVRecordRef };
 
 mod value;
 mod general;
@@ -39,30 +41,40 @@ use combinator::function:: { function_record };
 use combinator::line:: { lines_record };
 
 #[inline]
-pub fn report<I>(input: State<I>) -> ParseResult<Vec<LCOVRecord>, State<I>> where I: Stream<Item=char> {
-    let record_parser = parser(record::<I>);
+pub fn report(input: State<&str>) -> ParseResult<Vec<LCOVRecord>, State<&str>> {
+    let record_parser = parser(record).map( LCOVRecord::from );
     many(record_parser).parse_stream(input)
 }
 
 #[inline]
-pub fn record<I>(input: State<I>) -> ParseResult<LCOVRecord, State<I>> where I: Stream<Item=char> {
-    parser(general_record::<I>)
-        .or(parser(function_record::<I>))
-        .or(parser(lines_record::<I>))
-        .or(parser(branch_record::<I>))
-        .parse_stream(input)
+pub fn record<'a>(input: State<&'a str>) -> ParseResult<LCOVRecordRef<'a>, State<&'a str>> {
+    // Select the parser from the tag of the record, so that the other parsers are not tried
+    let tag = input.input.split([':', '\r', '\n']).next().unwrap_or("");
+    match tag {
+        "end_of_record" | "TN" | "SF" | "DA" => general_record(input),
+        "FN" | "FNDA" | "FNL" | "FNA" | "FNF" | "FNH" => function_record(input),
+        "LH" | "LF" => lines_record(input),
+        "BRDA" | "BRF" | "BRH" => branch_record(input),
+        _ => {
+            parser(general_record)
+                .or(parser(function_record))
+                .or(parser(lines_record))
+                .or(parser(branch_record))
+                .parse_stream(input)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use combine:: { parser, Parser, State };
-    use record:: { LCOVRecord, LineData, FunctionName, FunctionData, BranchData };
+    use record:: { LCOVRecord, LineData, FunctionName, FunctionData, FunctionLine, FunctionAlias, BranchData };
 
     fn parse_record(input: &str) -> LCOVRecord {
         let input = State::new(input);
         let (result, _) = parser(record).parse(input).unwrap();
-        result
+        LCOVRecord::from(result)
     }
 
     #[test]
@@ -97,7 +109,18 @@ mod tests {
     #[test]
     fn function_name() {
         let result = parse_record("FN:5,main\n");
-        let func = FunctionName { name: "main".to_string(), line: 5 };
+        let func = FunctionName { name: "main".to_string(), line: 5, end: None };
+        assert_eq!(result, LCOVRecord::FunctionName(func));
+    }
+
+    #[test]
+    fn function_name_with_end_line() {
+        let result = parse_record("FN:5,10,main\n");
+        let func = FunctionName { name: "main".to_string(), line: 5, end: Some(10) };
+        assert_eq!(result, LCOVRecord::FunctionName(func));
+
+        let result = parse_record("FN:5,10abc\n");
+        let func = FunctionName { name: "10abc".to_string(), line: 5, end: None };
         assert_eq!(result, LCOVRecord::FunctionName(func));
     }
 
@@ -108,6 +131,27 @@ mod tests {
         assert_eq!(result, LCOVRecord::FunctionData(func_data));
     }
 
+    #[test]
+    fn function_line() {
+        let result = parse_record("FNL:0,5\n");
+        let func_line = FunctionLine { index: 0, start: 5, end: None };
+        assert_eq!(result, LCOVRecord::FunctionLine(func_line));
+    }
+
+    #[test]
+    fn function_line_with_end_line() {
+        let result = parse_record("FNL:0,5,10\n");
+        let func_line = FunctionLine { index: 0, start: 5, end: Some(10) };
+        assert_eq!(result, LCOVRecord::FunctionLine(func_line));
+    }
+
+    #[test]
+    fn function_alias() {
+        let result = parse_record("FNA:0,3,main\n");
+        let func_alias = FunctionAlias { index: 0, count: 3, name: "main".to_string() };
+        assert_eq!(result, LCOVRecord::FunctionAlias(func_alias));
+    }
+
     #[test]
     fn functions_found() {
         let result = parse_record("FNF:10\n");
@@ -135,14 +179,14 @@ mod tests {
     #[test]
     fn branch_data() {
         let result = parse_record("BRDA:1,2,3,-\n");
-        let branch = BranchData { line: 1, block: 2, branch: 3, taken: 0 };
+        let branch = BranchData { line: 1, block: 2, branch: 3, taken: None };
         assert_eq!(result, LCOVRecord::BranchData(branch));
     }
 
     #[test]
     fn branch_data_with_branch_times() {
         let result = parse_record("BRDA:1,2,3,4\n");
-        let branch = BranchData { line: 1, block: 2, branch: 3, taken: 4 };
+        let branch = BranchData { line: 1, block: 2, branch: 3, taken: Some(4) };
         assert_eq!(result, LCOVRecord::BranchData(branch));
     }
 
@@ -163,4 +207,47 @@ mod tests {
         let result = parse_record("end_of_record\n");
         assert_eq!(result, LCOVRecord::EndOfRecord);
     }
+
+    #[test]
+    fn execution_count_above_u32() {
+        let result = parse_record("DA:1,5000000000\n");
+        let line = LineData { line: 1, count: 5000000000, checksum: None };
+        assert_eq!(result, LCOVRecord::Data(line));
+
+        let result = parse_record("BRDA:1,2,3,18446744073709551615\n");
+        let branch = BranchData { line: 1, block: 2, branch: 3, taken: Some(u64::MAX) };
+        assert_eq!(result, LCOVRecord::BranchData(branch));
+    }
+
+    #[test]
+    fn out_of_range_number() {
+        let parse = | input | parser(record).parse(State::new(input)).map( | (record, _) | LCOVRecord::from(record) );
+
+        assert!(parse("DA:1,18446744073709551616\n").is_err());
+        assert!(parse("DA:4294967296,1\n").is_err());
+        assert!(parse("FNF:4294967296\n").is_err());
+    }
+
+    #[test]
+    fn crlf_line_ending() {
+        let result = parse_record("SF:/path/to/source.rs\r\n");
+        assert_eq!(result, LCOVRecord::SourceFile("/path/to/source.rs".to_string()));
+
+        let result = parse_record("DA:1,2,3sdfjiji56\r\n");
+        let line = LineData { line: 1, count: 2, checksum: Some("3sdfjiji56".to_string()) };
+        assert_eq!(result, LCOVRecord::Data(line));
+
+        let result = parse_record("end_of_record\r\n");
+        assert_eq!(result, LCOVRecord::EndOfRecord);
+    }
+
+    #[test]
+    fn end_of_input() {
+        let result = parse_record("FNDA:5,main");
+        let func_data = FunctionData { name: "main".to_string(), count: 5 };
+        assert_eq!(result, LCOVRecord::FunctionData(func_data));
+
+        let result = parse_record("end_of_record");
+        assert_eq!(result, LCOVRecord::EndOfRecord);
+    }
 }
@@ -6,17 +6,33 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
-use combine:: { many1, satisfy, Parser, ParseResult, State, Stream };
-use combine::char:: { digit };
+use combine:: { optional, token, eof, Parser, ParseResult, State };
+use combine::char:: { newline };
+use combine::range:: { take_while1 };
 
-pub fn to_integer<I>(input: State<I>) -> ParseResult<u32, State<I>> where I: Stream<Item=char> {
-    many1( digit() )
-        .map( |s: String| s.parse::<u32>().unwrap() )
+pub fn to_integer<'a>(input: State<&'a str>) -> ParseResult<u32, State<&'a str>> {
+    take_while1( | c: char | c.is_ascii_digit() )
+        .and_then( | s: &'a str | s.parse::<u32>() )
         .parse_stream(input)
 }
 
-pub fn to_string<I>(input: State<I>) -> ParseResult<String, State<I>> where I: Stream<Item=char> {
-    many1( satisfy( | c: char | c != '\n' ) )
-        .map( | s: String | s )
+/// The execution count, which is wider than the other numbers
+pub fn to_count<'a>(input: State<&'a str>) -> ParseResult<u64, State<&'a str>> {
+    take_while1( | c: char | c.is_ascii_digit() )
+        .and_then( | s: &'a str | s.parse::<u64>() )
+        .parse_stream(input)
+}
+
+pub fn to_str(input: State<&str>) -> ParseResult<&str, State<&str>> {
+    take_while1( | c: char | c != '\n' && c != '\r' )
+        .parse_stream(input)
+}
+
+/// The terminator of the record, LF, CRLF or the end of the input
+pub fn end_of_line(input: State<&str>) -> ParseResult<(), State<&str>> {
+    optional(token('\r'))
+        .with(newline())
+        .map( | _ | () )
+        .or(eof())
         .parse_stream(input)
 }
@@ -0,0 +1,319 @@
+// Copyright (c) 2015-2016 lcov-parser developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Reader and writer of the compressed tracefile.
+//!
+//! The compression of the input is detected by the magic bytes, and the compression of the output by the extension.
+//! Decoding and encoding are enabled by the following features.
+//!
+//! * gzip: `.gz`
+//! * zstd: `.zst`
+
+#[cfg(feature = "gzip")]
+extern crate flate2;
+#[cfg(feature = "zstd")]
+extern crate zstd;
+
+use std::fs:: { File, OpenOptions };
+use std::io:: { Result as IOResult, Error as IOError, ErrorKind, Read, Write, BufRead, BufReader, BufWriter };
+use std::path:: { Path };
+use std::fmt;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd
+}
+
+impl Compression {
+    /// Detect the compression from the first bytes of the file
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { Compression };
+    ///
+    /// assert_eq!(Compression::from_magic(&[0x1f, 0x8b, 0x08]), Compression::Gzip);
+    /// assert_eq!(Compression::from_magic(b"TN:test\n"), Compression::None);
+    /// ```
+    pub fn from_magic(bytes: &[u8]) -> Self {
+        if bytes.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if bytes.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+    /// Detect the compression from the extension of the path
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { Compression };
+    ///
+    /// assert_eq!(Compression::from_path("coverage.info.gz"), Compression::Gzip);
+    /// assert_eq!(Compression::from_path("coverage.info.zst"), Compression::Zstd);
+    /// assert_eq!(Compression::from_path("coverage.info"), Compression::None);
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None
+        }
+    }
+    /// Returns true if the compression is supported by the enabled features
+    pub fn is_supported(self) -> bool {
+        match self {
+            Compression::None => true,
+            Compression::Gzip => cfg!(feature = "gzip"),
+            Compression::Zstd => cfg!(feature = "zstd")
+        }
+    }
+    fn feature(self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd"
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Compression::None => write!(f, "uncompressed"),
+            Compression::Gzip => write!(f, "gzip"),
+            Compression::Zstd => write!(f, "zstd")
+        }
+    }
+}
+
+enum Decoder {
+    Plain(BufReader<File>),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::read::MultiGzDecoder<BufReader<File>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::read::Decoder<'static, BufReader<File>>)
+}
+
+/// Reader of the tracefile, which decodes the compressed file on the fly
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+/// use lcov_parser:: { TracefileReader, Compression };
+///
+/// let mut reader = TracefileReader::open("tests/fixtures/parser/report.lcov").unwrap();
+/// let mut content = String::new();
+/// reader.read_to_string(&mut content).unwrap();
+///
+/// assert_eq!(reader.compression(), Compression::None);
+/// assert!(content.starts_with("TN:test\n"));
+/// ```
+pub struct TracefileReader {
+    compression: Compression,
+    decoder: Decoder
+}
+
+impl TracefileReader {
+    /// Open the file, an error of `ErrorKind::InvalidData` is returned when the feature of the compression is disabled
+    pub fn open<P: AsRef<Path>>(path: P) -> IOResult<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let compression = Compression::from_magic(reader.fill_buf()?);
+
+        let decoder = match compression {
+            Compression::None => Decoder::Plain(reader),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Decoder::Gzip(flate2::read::MultiGzDecoder::new(reader)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Decoder::Zstd(zstd::stream::read::Decoder::with_buffer(reader)?),
+            #[allow(unreachable_patterns)]
+            _ => return Err(unsupported(compression))
+        };
+        Ok(TracefileReader { compression, decoder })
+    }
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+}
+
+impl Read for TracefileReader {
+    fn read(&mut self, buf: &mut [u8]) -> IOResult<usize> {
+        match self.decoder {
+            Decoder::Plain(ref mut reader) => reader.read(buf),
+            #[cfg(feature = "gzip")]
+            Decoder::Gzip(ref mut reader) => reader.read(buf),
+            #[cfg(feature = "zstd")]
+            Decoder::Zstd(ref mut reader) => reader.read(buf)
+        }
+    }
+}
+
+enum Encoder {
+    Plain(BufWriter<File>),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, BufWriter<File>>)
+}
+
+/// Writer of the tracefile, which compresses the output by the extension of the path
+///
+/// # Examples
+///
+/// ```
+/// extern crate tempdir;
+/// extern crate lcov_parser;
+///
+/// use std::io::Write;
+/// use tempdir::TempDir;
+/// use lcov_parser:: { TracefileWriter, Compression };
+///
+/// # fn main() {
+/// let tmp_dir = TempDir::new("writer").unwrap();
+/// let mut writer = TracefileWriter::create(tmp_dir.path().join("report.info")).unwrap();
+/// writer.write_all(b"TN:test\n").unwrap();
+///
+/// assert_eq!(writer.compression(), Compression::None);
+/// writer.finish().unwrap();
+/// # }
+/// ```
+pub struct TracefileWriter {
+    compression: Compression,
+    encoder: Encoder
+}
+
+impl TracefileWriter {
+    /// Create the file, an error of `ErrorKind::InvalidData` is returned when the feature of the compression is disabled
+    pub fn create<P: AsRef<Path>>(path: P) -> IOResult<Self> {
+        let compression = Compression::from_path(path.as_ref());
+        if !compression.is_supported() {
+            return Err(unsupported(compression));
+        }
+        let output = OpenOptions::new().create(true).write(true).truncate(true).open(path)?;
+        let writer = BufWriter::new(output);
+
+        let encoder = match compression {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(writer, flate2::Compression::default())),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Encoder::Zstd(zstd::stream::write::Encoder::new(writer, 0)?),
+            _ => Encoder::Plain(writer)
+        };
+        Ok(TracefileWriter { compression, encoder })
+    }
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+    /// Write the end of the compressed stream and flush the file
+    #[allow(clippy::infallible_destructuring_match)]
+    pub fn finish(self) -> IOResult<()> {
+        let mut writer = match self.encoder {
+            Encoder::Plain(writer) => writer,
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.finish()?
+        };
+        writer.flush()
+    }
+}
+
+impl Write for TracefileWriter {
+    fn write(&mut self, buf: &[u8]) -> IOResult<usize> {
+        match self.encoder {
+            Encoder::Plain(ref mut writer) => writer.write(buf),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(ref mut writer) => writer.write(buf),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(ref mut writer) => writer.write(buf)
+        }
+    }
+    fn flush(&mut self) -> IOResult<()> {
+        match self.encoder {
+            Encoder::Plain(ref mut writer) => writer.flush(),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(ref mut writer) => writer.flush(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(ref mut writer) => writer.flush()
+        }
+    }
+}
+
+fn unsupported(compression: Compression) -> IOError {
+    let message = format!("{} compressed tracefile requires the `{}` feature", compression, compression.feature());
+    IOError::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tempdir;
+
+    use self::tempdir::TempDir;
+    use std::io:: { Read, Write };
+    use compression:: { Compression, TracefileReader, TracefileWriter };
+
+    fn roundtrip(file_name: &str) -> (Compression, String) {
+        let tmp_dir = TempDir::new("compression").expect("create temp dir");
+        let file_path = tmp_dir.path().join(file_name);
+
+        let mut writer = TracefileWriter::create(&file_path).unwrap();
+        writer.write_all(b"TN:test\nend_of_record\n").unwrap();
+        writer.finish().unwrap();
+
+        let mut reader = TracefileReader::open(&file_path).unwrap();
+        let mut content = String::new();
+        reader.read_to_string(&mut content).unwrap();
+        (reader.compression(), content)
+    }
+
+    #[test]
+    fn plain() {
+        let (compression, content) = roundtrip("report.info");
+        assert_eq!(compression, Compression::None);
+        assert_eq!(content, "TN:test\nend_of_record\n");
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn gzip() {
+        let (compression, content) = roundtrip("report.info.gz");
+        assert_eq!(compression, Compression::Gzip);
+        assert_eq!(content, "TN:test\nend_of_record\n");
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn zstd() {
+        let (compression, content) = roundtrip("report.info.zst");
+        assert_eq!(compression, Compression::Zstd);
+        assert_eq!(content, "TN:test\nend_of_record\n");
+    }
+
+    #[test]
+    #[cfg(not(feature = "gzip"))]
+    fn gzip_without_feature() {
+        use std::io:: { ErrorKind };
+
+        let tmp_dir = TempDir::new("compression").expect("create temp dir");
+        let file_path = tmp_dir.path().join("report.info.gz");
+        let result = TracefileWriter::create(&file_path);
+        assert_eq!(result.err().map(|err| err.kind()), Some(ErrorKind::InvalidData));
+        assert!(!file_path.exists());
+
+        let result = TracefileReader::open("tests/fixtures/parser/report.lcov.gz");
+        assert_eq!(result.err().map(|err| err.kind()), Some(ErrorKind::InvalidData));
+    }
+}
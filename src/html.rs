@@ -0,0 +1,500 @@
+// Copyright (c) 2015-2016 lcov-parser developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! HTML report of the coverage, comparable to genhtml.
+//!
+//! The output directory has the following pages.
+//!
+//! * `index.html`: coverage of the directories
+//! * `<directory>/index.html`: coverage of the source files in the directory
+//! * `<directory>/<file>.gcov.html`: functions and source lines of the file, the tests of the file are combined
+
+use std::fs;
+use std::io:: { Result, Error, ErrorKind, Write, BufWriter };
+use std::path:: { Path, PathBuf, Component };
+use std::collections:: { BTreeMap, BTreeSet };
+use report:: { Report, source_path };
+use report::test:: { Test };
+use report::summary:: { Summary };
+use report::counter:: { HitCounter, FoundCounter };
+use report::coverage:: { Coverage, CoverageSummary };
+use report::branch:: { BranchBlocks };
+use escape:: { escape };
+
+const STYLESHEET: &str = "lcov.css";
+const HIGH_LIMIT: f64 = 90.0;
+const MEDIUM_LIMIT: f64 = 75.0;
+
+/// Write the report as HTML pages
+///
+/// # Examples
+///
+/// ```
+/// extern crate tempdir;
+/// extern crate lcov_parser;
+///
+/// use tempdir::TempDir;
+/// use lcov_parser:: { HtmlWriter, merge_files };
+///
+/// # fn main() {
+/// let report = merge_files(&[ "tests/fixtures/merged/eq_checksum/fixture1.info" ]).unwrap();
+/// let tmp_dir = TempDir::new("html").unwrap();
+///
+/// let mut writer = HtmlWriter::new(tmp_dir.path());
+/// writer.set_source_root("tests/fixtures/merged/eq_checksum");
+/// writer.write(&report).unwrap();
+///
+/// assert!(tmp_dir.path().join("index.html").exists());
+/// # }
+/// ```
+pub struct HtmlWriter {
+    output_dir: PathBuf,
+    source_root: Option<PathBuf>,
+    title: String
+}
+
+impl HtmlWriter {
+    pub fn new<P: AsRef<Path>>(output_dir: P) -> Self {
+        HtmlWriter {
+            output_dir: output_dir.as_ref().to_path_buf(),
+            source_root: None,
+            title: "LCOV - code coverage report".to_string()
+        }
+    }
+    pub fn set_title<S: Into<String>>(&mut self, title: S) {
+        self.title = title.into();
+    }
+    /// Set the directory that the source files are read from.
+    /// The source file is read from the path of the report if the root is not set,
+    /// otherwise the path is joined to the root, including the absolute path.
+    pub fn set_source_root<P: AsRef<Path>>(&mut self, source_root: P) {
+        self.source_root = Some(source_root.as_ref().to_path_buf());
+    }
+    /// Write the pages, the source directories which have the same output directory such as `src/./lib` and `src/lib`
+    /// are written as one directory, and the files of the same name in it are suffixed with `~2`, `~3` and so on in the order of the source files.
+    pub fn write(&self, report: &Report) -> Result<()> {
+        let mut directories: BTreeMap<PathBuf, SourceDirectory> = BTreeMap::new();
+
+        for (source_name, file) in report.files().iter() {
+            let test = file.combined().map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+            let path = Path::new(source_name);
+            let directory = path.parent()
+                .map(|directory| directory.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let file_name = path.file_name()
+                .map(|file_name| file_name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| source_name.clone());
+
+            let source_directory = directories.entry(output_directory(&directory)).or_insert_with(|| SourceDirectory {
+                name: directory,
+                page_names: BTreeSet::new(),
+                pages: vec![]
+            });
+            let page_name = source_directory.unique_page_name(&file_name);
+            source_directory.pages.push(SourcePage {
+                source_name,
+                page_name,
+                summary: test.coverage_summary(),
+                test
+            });
+        }
+
+        fs::create_dir_all(&self.output_dir)?;
+        fs::write(self.output_dir.join(STYLESHEET), CSS)?;
+        self.write_index(report.coverage_summary(), &directories)?;
+
+        for (output_dir, directory) in directories.iter() {
+            fs::create_dir_all(self.output_dir.join(output_dir))?;
+            self.write_directory(&directory.name, output_dir, &directory.pages)?;
+            for page in directory.pages.iter() {
+                self.write_source(&directory.name, output_dir, page)?;
+            }
+        }
+        Ok(())
+    }
+    fn write_index(&self, summary: CoverageSummary, directories: &BTreeMap<PathBuf, SourceDirectory>) -> Result<()> {
+        let mut output = BufWriter::new(fs::File::create(self.output_dir.join("index.html"))?);
+
+        write_header(&mut output, &self.title, "", "top level", &summary)?;
+        write_table_header(&mut output, "Directory")?;
+        for (output_dir, directory) in directories.iter() {
+            let summary = directory.pages.iter().fold(CoverageSummary::default(), |summary, page| summary + page.summary);
+            let link = format!("{}/index.html", url_path(output_dir));
+            write_table_row(&mut output, &link, &display_directory(&directory.name), &summary)?;
+        }
+        writeln!(output, "</table>")?;
+        write_footer(&mut output)
+    }
+    fn write_directory(&self, directory: &str, output_dir: &Path, pages: &[SourcePage]) -> Result<()> {
+        let mut output = BufWriter::new(fs::File::create(self.output_dir.join(output_dir).join("index.html"))?);
+        let prefix = relative_prefix(output_dir);
+        let location = format!("<a href=\"{}index.html\">top level</a> - {}", prefix, escape(&display_directory(directory)));
+        let summary = pages.iter().fold(CoverageSummary::default(), |summary, page| summary + page.summary);
+
+        write_header(&mut output, &self.title, &prefix, &location, &summary)?;
+        write_table_header(&mut output, "Filename")?;
+        for page in pages.iter() {
+            let link = format!("{}.gcov.html", url_escape(&page.page_name));
+            write_table_row(&mut output, &link, &page.page_name, &page.summary)?;
+        }
+        writeln!(output, "</table>")?;
+        write_footer(&mut output)
+    }
+    fn write_source(&self, directory: &str, output_dir: &Path, page: &SourcePage) -> Result<()> {
+        let file_path = self.output_dir.join(output_dir).join(format!("{}.gcov.html", page.page_name));
+        let mut output = BufWriter::new(fs::File::create(file_path)?);
+        let prefix = relative_prefix(output_dir);
+        let location = format!("<a href=\"{}index.html\">top level</a> - <a href=\"index.html\">{}</a> - {}",
+            prefix, escape(&display_directory(directory)), escape(&page.page_name));
+
+        write_header(&mut output, &self.title, &prefix, &location, &page.summary)?;
+        write_functions(&mut output, &page.test)?;
+
+        let source_path = source_path(self.source_root.as_deref(), page.source_name);
+        let source = fs::read(&source_path)
+            .map(|source| String::from_utf8_lossy(&source).into_owned())
+            .ok();
+        if source.is_none() {
+            writeln!(output, "<p class=\"notice\">Source is not available: {}</p>", escape(&source_path.to_string_lossy()))?;
+        }
+        write_source_lines(&mut output, &page.test, source.as_deref().unwrap_or(""))?;
+        write_footer(&mut output)
+    }
+}
+
+/// Source directories written to the same output directory, the name is the first of the directories
+struct SourceDirectory<'a> {
+    name: String,
+    page_names: BTreeSet<String>,
+    pages: Vec<SourcePage<'a>>
+}
+
+impl<'a> SourceDirectory<'a> {
+    fn unique_page_name(&mut self, file_name: &str) -> String {
+        let mut page_name = file_name.to_string();
+        let mut suffix = 1;
+        while self.page_names.contains(&page_name) {
+            suffix += 1;
+            page_name = format!("{}~{}", file_name, suffix);
+        }
+        self.page_names.insert(page_name.clone());
+        page_name
+    }
+}
+
+struct SourcePage<'a> {
+    source_name: &'a str,
+    page_name: String,
+    summary: CoverageSummary,
+    test: Test
+}
+
+fn write_header<W: Write>(output: &mut W, title: &str, prefix: &str, location: &str, summary: &CoverageSummary) -> Result<()> {
+    writeln!(output, "<!DOCTYPE html>")?;
+    writeln!(output, "<html>")?;
+    writeln!(output, "<head>")?;
+    writeln!(output, "  <meta charset=\"utf-8\">")?;
+    writeln!(output, "  <title>{}</title>", escape(title))?;
+    writeln!(output, "  <link rel=\"stylesheet\" href=\"{}{}\">", prefix, STYLESHEET)?;
+    writeln!(output, "</head>")?;
+    writeln!(output, "<body>")?;
+    writeln!(output, "<h1>{}</h1>", escape(title))?;
+    writeln!(output, "<p class=\"location\">Current view: {}</p>", location)?;
+    writeln!(output, "<table class=\"summary\">")?;
+    writeln!(output, "  <tr><th></th><th>Hit</th><th>Total</th><th>Coverage</th></tr>")?;
+    write_summary_row(output, "Lines", &summary.lines)?;
+    write_summary_row(output, "Functions", &summary.functions)?;
+    write_summary_row(output, "Branches", &summary.branches)?;
+    writeln!(output, "</table>")
+}
+
+fn write_summary_row<W: Write>(output: &mut W, label: &str, coverage: &Coverage) -> Result<()> {
+    writeln!(output, "  <tr><th>{}:</th><td>{}</td><td>{}</td><td class=\"{}\">{}</td></tr>",
+        label, coverage.hit_count(), coverage.found_count(), rate_class(coverage), rate(coverage))
+}
+
+fn write_footer<W: Write>(output: &mut W) -> Result<()> {
+    writeln!(output, "</body>")?;
+    writeln!(output, "</html>")?;
+    output.flush()
+}
+
+fn write_table_header<W: Write>(output: &mut W, name: &str) -> Result<()> {
+    writeln!(output, "<table class=\"files\">")?;
+    writeln!(output, "  <tr><th>{}</th><th colspan=\"3\">Line Coverage</th><th colspan=\"2\">Functions</th><th colspan=\"2\">Branches</th></tr>", name)
+}
+
+fn write_table_row<W: Write>(output: &mut W, link: &str, name: &str, summary: &CoverageSummary) -> Result<()> {
+    let lines = &summary.lines;
+    let width = lines.percentage().unwrap_or(0.0).round();
+
+    writeln!(output, "  <tr>")?;
+    writeln!(output, "    <td class=\"name\"><a href=\"{}\">{}</a></td>", link, escape(name))?;
+    writeln!(output, "    <td class=\"bar\"><div class=\"bar\"><div class=\"{}\" style=\"width: {}%\"></div></div></td>", rate_class(lines), width)?;
+    write_coverage_cells(output, lines)?;
+    write_coverage_cells(output, &summary.functions)?;
+    write_coverage_cells(output, &summary.branches)?;
+    writeln!(output, "  </tr>")
+}
+
+fn write_coverage_cells<W: Write>(output: &mut W, coverage: &Coverage) -> Result<()> {
+    writeln!(output, "    <td class=\"{}\">{}</td><td class=\"{}\">{} / {}</td>",
+        rate_class(coverage), rate(coverage), rate_class(coverage), coverage.hit_count(), coverage.found_count())
+}
+
+fn write_functions<W: Write>(output: &mut W, test: &Test) -> Result<()> {
+    if test.functions().is_empty() {
+        return Ok(());
+    }
+    writeln!(output, "<table class=\"functions\">")?;
+    writeln!(output, "  <tr><th>Function Name</th><th>Line</th><th>Hit count</th></tr>")?;
+    for (name, function) in test.functions().iter() {
+        let class = if function.is_hit() { "hit" } else { "miss" };
+        writeln!(output, "  <tr><td class=\"{}\"><a href=\"#L{}\">{}</a></td><td>{}</td><td class=\"{}\">{}</td></tr>",
+            class, function.line_number(), escape(name), function.line_number(), class, function.execution_count())?;
+    }
+    writeln!(output, "</table>")
+}
+
+fn write_source_lines<W: Write>(output: &mut W, test: &Test, source: &str) -> Result<()> {
+    let source_lines: Vec<&str> = source.lines().collect();
+    let last_line = test.lines().iter().next_back().map(|(line_number, _)| *line_number)
+        .max(test.branches().iter().next_back().map(|(line_number, _)| *line_number))
+        .unwrap_or(0);
+    let line_count = (source_lines.len() as u32).max(last_line);
+
+    writeln!(output, "<pre class=\"source\">")?;
+    for line_number in 1..=line_count {
+        let text = source_lines.get(line_number as usize - 1).cloned().unwrap_or("");
+        let markers = test.branches().get(&line_number).map(branch_markers).unwrap_or_default();
+        match test.lines().get(&line_number) {
+            Some(line) => {
+                let class = if line.is_hit() { "hit" } else { "miss" };
+                writeln!(output, "<span id=\"L{}\" class=\"{}\"><span class=\"number\">{:>8}</span> {}<span class=\"count\">{:>12}</span> : {}</span>",
+                    line_number, class, line_number, markers, line.execution_count(), escape(text))?;
+            },
+            None => {
+                writeln!(output, "<span id=\"L{}\"><span class=\"number\">{:>8}</span> {}{:>12}   {}</span>",
+                    line_number, line_number, markers, "", escape(text))?;
+            }
+        }
+    }
+    writeln!(output, "</pre>")
+}
+
+/// Markers of the branches on the line, `+` taken, `-` not taken and `#` not executed
+fn branch_markers(blocks: &BranchBlocks) -> String {
+    let mut markers = String::from("<span class=\"branches\">[ ");
+    for (unit, branch) in blocks.iter() {
+        let (class, marker, title) = match branch.execution_count() {
+            Some(&0) => ("miss", "-", format!("Branch {} was not taken", unit)),
+            Some(count) => ("hit", "+", format!("Branch {} was taken {} times", unit, count)),
+            None => ("none", "#", format!("Branch {} was not executed", unit))
+        };
+        markers.push_str(&format!("<span class=\"{}\" title=\"{}\">{}</span> ", class, title, marker));
+    }
+    markers.push_str("]</span> ");
+    markers
+}
+
+fn rate(coverage: &Coverage) -> String {
+    match coverage.percentage() {
+        Some(percentage) => format!("{:.1} %", percentage),
+        None => "-".to_string()
+    }
+}
+
+fn rate_class(coverage: &Coverage) -> &'static str {
+    match coverage.percentage() {
+        Some(percentage) if percentage >= HIGH_LIMIT => "high",
+        Some(percentage) if percentage >= MEDIUM_LIMIT => "medium",
+        Some(_) => "low",
+        None => "none"
+    }
+}
+
+fn display_directory(directory: &str) -> String {
+    if directory.is_empty() {
+        ".".to_string()
+    } else {
+        directory.to_string()
+    }
+}
+
+/// Output directory of the source directory, which is kept inside of the output.
+/// The root is `_root`, the current directory is `_cur`, the parent directory is `_up`, and the empty directory is `_`.
+/// The names starting with `_` are prefixed with another `_`, so that they do not collide with the names above.
+fn output_directory(directory: &str) -> PathBuf {
+    let mut output_dir = PathBuf::new();
+    for component in Path::new(directory).components() {
+        match component {
+            Component::Normal(name) if name.to_string_lossy().starts_with('_') => {
+                output_dir.push(format!("_{}", name.to_string_lossy()));
+            },
+            Component::Normal(name) => output_dir.push(name),
+            Component::RootDir => output_dir.push("_root"),
+            Component::CurDir => output_dir.push("_cur"),
+            Component::ParentDir => output_dir.push("_up"),
+            Component::Prefix(_) => {}
+        }
+    }
+    if output_dir.as_os_str().is_empty() {
+        output_dir.push("_");
+    }
+    output_dir
+}
+
+fn relative_prefix(output_dir: &Path) -> String {
+    "../".repeat(output_dir.components().count())
+}
+
+fn url_path(path: &Path) -> String {
+    path.components()
+        .map(|component| url_escape(&component.as_os_str().to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn url_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("%{:02X}", byte))
+        }
+    }
+    escaped
+}
+
+const CSS: &str = r#"body { font-family: sans-serif; color: #000; background: #fff; }
+h1 { font-size: 1.4em; border-bottom: 3px solid #6688d4; padding-bottom: 4px; }
+a { color: #284fa8; }
+table { border-collapse: collapse; margin-bottom: 1.5em; }
+th { background: #6688d4; color: #fff; padding: 2px 8px; }
+td { padding: 2px 8px; }
+table.summary th { background: none; color: #284fa8; text-align: right; }
+table.summary td, table.files td { text-align: right; background: #dae7fe; }
+table.files td.name, table.functions td { text-align: left; background: #dae7fe; }
+td.high, span.hit, td.hit { background: #a7fc9d; }
+td.medium { background: #ffea20; }
+td.low, span.miss, td.miss { background: #ff6230; }
+td.none, span.none { background: #dae7fe; }
+div.bar { width: 100px; height: 10px; background: #ff6230; border: 1px solid #000; }
+div.bar div { height: 10px; }
+div.bar div.high { background: #a7fc9d; }
+div.bar div.medium { background: #ffea20; }
+div.bar div.low { background: #ff6230; }
+p.notice { color: #ff0000; }
+pre.source { font-family: monospace; }
+pre.source span.number { background: #efe383; }
+"#;
+
+#[cfg(test)]
+mod tests {
+    extern crate tempdir;
+
+    use self::tempdir::TempDir;
+    use std::fs;
+    use std::path:: { Path };
+    use merger:: { merge_files };
+    use merger:: { ReportMerger };
+    use html:: { HtmlWriter, url_escape, output_directory };
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn write_report() {
+        let report = merge_files(&[ "tests/fixtures/merged/eq_checksum/fixture1.info" ]).unwrap();
+        let tmp_dir = TempDir::new("html").expect("create temp dir");
+
+        let mut writer = HtmlWriter::new(tmp_dir.path());
+        writer.set_title("fixture");
+        writer.set_source_root("tests/fixtures/merged/eq_checksum");
+        writer.write(&report).unwrap();
+
+        let index = read(&tmp_dir.path().join("index.html"));
+        assert!(index.contains("<title>fixture</title>"));
+        assert!(index.contains("<a href=\"_root/index.html\">/</a>"));
+        assert!(index.contains("<td>23</td><td>25</td><td class=\"high\">92.0 %</td>"));
+
+        let directory = read(&tmp_dir.path().join("_root/index.html"));
+        assert!(directory.contains("<a href=\"func2.c.gcov.html\">func2.c</a>"));
+        assert!(directory.contains("<td class=\"medium\">77.8 %</td>"));
+
+        let source = read(&tmp_dir.path().join("_root/func2.c.gcov.html"));
+        assert!(source.contains("<a href=\"#L5\">func2</a>"));
+        assert!(source.contains(concat!(
+            "<span id=\"L8\" class=\"hit\"><span class=\"number\">       8</span> ",
+            "<span class=\"branches\">[ <span class=\"hit\" title=\"Branch 0-0 was taken 1 times\">+</span> ",
+            "<span class=\"miss\" title=\"Branch 0-1 was not taken\">-</span> ]</span> ",
+            "<span class=\"count\">           1</span> : "
+        )));
+        assert!(source.contains("<span id=\"L12\" class=\"miss\">"));
+        assert!(!source.contains("Source is not available"));
+    }
+
+    #[test]
+    fn write_without_source() {
+        let report = merge_files(&[ "tests/fixtures/merged/not_executed_branch/fixture.info" ]).unwrap();
+        let tmp_dir = TempDir::new("html").expect("create temp dir");
+
+        HtmlWriter::new(tmp_dir.path().join("output")).write(&report).unwrap();
+
+        let source = read(&tmp_dir.path().join("output/_root/fixture.c.gcov.html"));
+        assert!(source.contains("<p class=\"notice\">Source is not available: /fixture.c</p>"));
+        assert!(source.contains("<span class=\"none\" title=\"Branch 0-0 was not executed\">#</span>"));
+        assert!(source.contains("<span id=\"L6\" class=\"miss\">"));
+    }
+
+    #[test]
+    fn output_directories() {
+        assert_eq!(output_directory("/path/to"), Path::new("_root/path/to"));
+        assert_eq!(output_directory("path/to"), Path::new("path/to"));
+        assert_eq!(output_directory("../src"), Path::new("_up/src"));
+        assert_eq!(output_directory("./src"), Path::new("_cur/src"));
+        assert_eq!(output_directory("_up/_root"), Path::new("__up/__root"));
+        assert_eq!(output_directory(""), Path::new("_"));
+        assert_eq!(output_directory("_"), Path::new("__"));
+        assert_eq!(output_directory("/"), Path::new("_root"));
+    }
+
+    #[test]
+    fn write_directories_of_same_name() {
+        let mut merger = ReportMerger::new();
+        merger.merge_reader("TN:\nSF:/src/a.c\nDA:1,1\nend_of_record\nSF:src/a.c\nDA:1,0\nend_of_record\n".as_bytes()).unwrap();
+        let tmp_dir = TempDir::new("html").expect("create temp dir");
+
+        HtmlWriter::new(tmp_dir.path()).write(&merger.report()).unwrap();
+        assert!(read(&tmp_dir.path().join("_root/src/a.c.gcov.html")).contains("<span id=\"L1\" class=\"hit\">"));
+        assert!(read(&tmp_dir.path().join("src/a.c.gcov.html")).contains("<span id=\"L1\" class=\"miss\">"));
+
+        let mut merger = ReportMerger::new();
+        merger.merge_reader(concat!(
+            "TN:\nSF:src/lib/a.c\nDA:1,1\nend_of_record\n",
+            "SF:src/./lib/a.c\nDA:1,0\nend_of_record\n",
+            "SF:src/./lib/b.c\nDA:1,0\nend_of_record\n"
+        ).as_bytes()).unwrap();
+        let output_dir = tmp_dir.path().join("same_output");
+
+        HtmlWriter::new(&output_dir).write(&merger.report()).unwrap();
+        let directory = read(&output_dir.join("src/lib/index.html"));
+        assert!(directory.contains("<a href=\"a.c.gcov.html\">a.c</a>"));
+        assert!(directory.contains("<a href=\"a.c~2.gcov.html\">a.c~2</a>"));
+        assert!(directory.contains("<a href=\"b.c.gcov.html\">b.c</a>"));
+        assert!(read(&output_dir.join("src/lib/a.c.gcov.html")).contains("<span id=\"L1\" class=\"miss\">"));
+        assert!(read(&output_dir.join("src/lib/a.c~2.gcov.html")).contains("<span id=\"L1\" class=\"hit\">"));
+        assert_eq!(read(&output_dir.join("index.html")).matches("src/lib/index.html").count(), 1);
+    }
+
+    #[test]
+    fn escape_url() {
+        assert_eq!(url_escape("a b#.c"), "a%20b%23.c");
+    }
+}
@@ -11,6 +11,9 @@
 #![doc(html_root_url = "https://holyshared.github.io/lcov-parser/")]
 
 extern crate combine;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 
 #[macro_use]
 mod macros;
@@ -19,8 +22,22 @@ mod record;
 mod report;
 mod combinator;
 mod parser;
+mod compression;
+mod escape;
+mod html;
+mod patch;
+#[cfg(feature = "cobertura")]
+mod cobertura;
 
 pub use self::record::*;
 pub use self::parser::*;
-pub use self::merger:: { merge_files };
+pub use self::merger:: { merge_files, ReportMerger, PathRule };
+#[cfg(feature = "rayon")]
+pub use self::merger:: { merge_files_parallel };
+pub use self::merger::ops::*;
 pub use self::report::*;
+pub use self::compression::*;
+pub use self::html:: { HtmlWriter };
+pub use self::patch::*;
+#[cfg(feature = "cobertura")]
+pub use self::cobertura::*;
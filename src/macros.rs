@@ -10,7 +10,7 @@
 macro_rules! impl_summary {
     ($dest:ty, $field:ident<$key:ty, $value:ty>) => {
         impl $crate::report::summary::Summary<$key, $value> for $dest {
-            fn iter(&self) -> ::std::collections::btree_map::Iter<$key, $value> {
+            fn iter(&self) -> ::std::collections::btree_map::Iter<'_, $key, $value> {
                 self.$field.iter()
             }
             fn contains_key(&self, key: &$key) -> bool {
@@ -70,7 +70,7 @@ macro_rules! impl_from_error {
         }
     };
     ($from:ty, $nest_dest:ident::$nest_item:ident=>$dest:ident::$item:ident) => {
-        impl From<$from> for MergeError {
+        impl ::std::convert::From<$from> for $dest {
             fn from(error: $from) -> Self {
                 $dest::$item($nest_dest::$nest_item(error))
             }
@@ -6,21 +6,48 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
-use std::path::Path;
+use std::path:: { Path, PathBuf };
 use std::convert::{ AsRef };
+use std::collections:: { BTreeSet, BTreeMap };
 use std::result:: { Result };
-use parser:: { LCOVParser, FromFile };
-use record:: { LCOVRecord, LineData, FunctionData as FunctionDataRecord, BranchData as BranchDataRecord, FunctionName };
+use std::io:: { Read };
+use parser:: { LCOVParser, ParseMode, RecordParseError };
+use record:: { LCOVRecord, LineData, FunctionData as FunctionDataRecord, BranchData as BranchDataRecord, FunctionName, FunctionLine, FunctionAlias };
 use report:: { Report };
-use report::test:: { Tests };
+use report::test:: { Test, Tests };
 use report::file:: { File, Files };
-use merger::ops:: { Merge, TryMerge, MergeError, TestError, ChecksumError, BranchError, FunctionError, MergeResult };
+use report::attribute:: { SourceFile, TestName, LineNumber, ExecutionCount };
+use merger::ops:: { Merge, TryMerge, MergeError, MergeContext, MergeWarning, MergePolicy, MergeConflict, MergeLine, CountOverflow, OverflowCounter, TestError, ChecksumError, MergeResult };
+use report::summary:: { Summary };
+use merger::path:: { PathRule };
+#[cfg(feature = "filter")]
+use report::filter:: { FileFilter };
 
 pub struct ReportMerger {
     test_name: Option<String>,
     source_name: Option<String>,
     tests: Tests,
-    files: Files
+    files: Files,
+    parse_mode: ParseMode,
+    warnings: Vec<MergeWarning>,
+    overflow_warning: bool,
+    overflows: Vec<CountOverflow>,
+    path_rules: Vec<PathRule>,
+    #[cfg(feature = "filter")]
+    file_filter: Option<FileFilter>,
+    excluded: bool,
+    merge_policy: MergePolicy,
+    conflicts: Vec<MergeConflict>,
+    input: Option<PathBuf>,
+    line: Option<u32>,
+    /// Tracefile and line of end_of_record of the first record of each source file, to locate the conflicts of the partial reports
+    record_locations: BTreeMap<SourceFile, (Option<PathBuf>, u32)>
+}
+
+impl Default for ReportMerger {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ReportMerger {
@@ -29,100 +56,593 @@ impl ReportMerger {
             test_name: None,
             source_name: None,
             tests: Tests::new(),
-            files: Files::new()
+            files: Files::new(),
+            parse_mode: ParseMode::Strict,
+            warnings: vec![],
+            overflow_warning: false,
+            overflows: vec![],
+            path_rules: vec![],
+            #[cfg(feature = "filter")]
+            file_filter: None,
+            excluded: false,
+            merge_policy: MergePolicy::Fail,
+            conflicts: vec![],
+            input: None,
+            line: None,
+            record_locations: BTreeMap::new()
         }
     }
+    /// Set the mode of the parser for unknown or malformed records
+    pub fn set_parse_mode(&mut self, mode: ParseMode) {
+        self.parse_mode = mode;
+    }
+    /// Returns the records skipped by the lenient parser
+    pub fn warnings(&self) -> &[MergeWarning] {
+        &self.warnings
+    }
+    /// Collect the execution counts saturated while merging the records into the report.
+    /// The check looks up every counter of the record, so it is disabled by default.
+    pub fn set_overflow_warning(&mut self, enabled: bool) {
+        self.overflow_warning = enabled;
+    }
+    /// Returns the execution counts saturated while merging
+    pub fn overflows(&self) -> &[CountOverflow] {
+        &self.overflows
+    }
+    /// Add the rule to rewrite the path of the source file, the rules are applied in the order of the addition.
+    /// The source files which have the same path after the rewriting are merged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { ReportMerger, PathRule };
+    ///
+    /// let mut merger = ReportMerger::new();
+    /// merger.add_path_rule(PathRule::Normalize);
+    /// merger.add_path_rule(PathRule::StripPrefix("/home/ci/".to_string()));
+    /// merger.merge_reader("TN:\nSF:/home/ci/src/a.c\nDA:1,1\nend_of_record\n".as_bytes()).unwrap();
+    /// merger.merge_reader("TN:\nSF:/home/ci/lib/../src/a.c\nDA:1,1\nend_of_record\n".as_bytes()).unwrap();
+    ///
+    /// let report = merger.report();
+    /// assert_eq!(report.len(), 1);
+    /// assert!(report.get("src/a.c").is_some());
+    /// ```
+    pub fn add_path_rule(&mut self, rule: PathRule) {
+        self.path_rules.push(rule);
+    }
+    /// Set the filter of the source files, the records of the excluded files are skipped without merging.
+    /// The filter is matched with the path rewritten by the rules.
+    #[cfg(feature = "filter")]
+    pub fn set_file_filter(&mut self, filter: FileFilter) {
+        self.file_filter = Some(filter);
+    }
+    /// Set the policy to resolve the conflicts of the checksums, the functions and the branches.
+    /// With the policy other than `MergePolicy::Fail`, the records of the functions and the branches
+    /// which fail to merge are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { ReportMerger, MergePolicy };
+    /// use lcov_parser::summary:: { Summary };
+    ///
+    /// let mut merger = ReportMerger::new();
+    /// merger.set_merge_policy(MergePolicy::KeepFirst);
+    /// merger.merge_reader("TN:\nSF:/a.c\nDA:1,1,abc\nend_of_record\n".as_bytes()).unwrap();
+    /// merger.merge_reader("TN:\nSF:/a.c\nDA:1,2,xyz\nend_of_record\n".as_bytes()).unwrap();
+    ///
+    /// let report = merger.report();
+    /// let test = report.get("/a.c").unwrap().get_test(&"".to_string()).unwrap();
+    ///
+    /// assert_eq!(test.lines().get(&1).unwrap().execution_count(), &1);
+    /// assert_eq!(merger.conflicts()[0].line_number(), Some(1));
+    /// ```
+    pub fn set_merge_policy(&mut self, policy: MergePolicy) {
+        self.merge_policy = policy;
+    }
+    /// Returns the conflicts resolved by the policy
+    pub fn conflicts(&self) -> &[MergeConflict] {
+        &self.conflicts
+    }
     pub fn merge<T: AsRef<Path>>(&mut self, files: &[T]) -> Result<Report, MergeError> {
         for file in files.iter() {
-            self.process_file(file)?;
+            self.merge_file(file)?;
+        }
+        Ok(self.report())
+    }
+    /// Parse the files in parallel and merge the partial reports in the order of the files.
+    /// The partial reports are merged as soon as they are parsed, and the merge stops at the first error found.
+    /// The error is returned as `MergeError::Context` with the path of the file that caused it,
+    /// which is not always the earliest of the files if several files fail.
+    #[cfg(feature = "rayon")]
+    pub fn merge_parallel<T: AsRef<Path> + Sync>(&mut self, files: &[T]) -> Result<Report, MergeError> {
+        use merger::rayon::prelude::*;
+
+        let merged = files.par_iter()
+            .map(|file| -> Result<ReportMerger, MergeError> {
+                let mut partial = self.partial();
+                partial.merge_file(file)?;
+                Ok(partial)
+            })
+            .try_reduce(|| self.partial(), |mut merged, partial| {
+                merged.append(partial)?;
+                Ok(merged)
+            })?;
+        self.append(merged)?;
+        Ok(self.report())
+    }
+    /// Merge the tracefile
+    pub fn merge_file<T: AsRef<Path>>(&mut self, file: T) -> Result<(), MergeError> {
+        let parser = LCOVParser::open(file.as_ref()).map_err(|err| {
+            let context = MergeContext { path: Some(file.as_ref().to_path_buf()), ..MergeContext::default() };
+            MergeError::Context(context, Box::new(err.into()))
+        })?;
+        self.process_parser(parser, Some(file.as_ref()))
+    }
+    /// Merge the tracefile read from the reader
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { ReportMerger };
+    /// use lcov_parser::summary:: { Summary };
+    ///
+    /// let mut merger = ReportMerger::new();
+    /// merger.merge_reader("TN:test\nSF:/a.c\nDA:1,1\nend_of_record\n".as_bytes()).unwrap();
+    /// merger.merge_reader("TN:test\nSF:/a.c\nDA:1,2\nend_of_record\n".as_bytes()).unwrap();
+    ///
+    /// let report = merger.report();
+    /// let test = report.get("/a.c").unwrap().get_test(&"test".to_string()).unwrap();
+    ///
+    /// assert_eq!(test.lines().get(&1).unwrap().execution_count(), &3);
+    /// ```
+    pub fn merge_reader<R: Read>(&mut self, reader: R) -> Result<(), MergeError> {
+        self.process_parser(LCOVParser::new(reader), None)
+    }
+    /// Merge the records, the error is returned as `MergeError::Context` without the path.
+    /// The line of the context is the position of the record, starting from 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { ReportMerger, LCOVRecord, LineData };
+    ///
+    /// let records = vec![
+    ///     LCOVRecord::TestName(Some("test".to_string())),
+    ///     LCOVRecord::SourceFile("/a.c".to_string()),
+    ///     LCOVRecord::Data(LineData { line: 1, count: 1, checksum: None }),
+    ///     LCOVRecord::EndOfRecord
+    /// ];
+    /// let mut merger = ReportMerger::new();
+    /// merger.merge_records(records).unwrap();
+    ///
+    /// assert_eq!(merger.report().len(), 1);
+    /// ```
+    pub fn merge_records<I: IntoIterator<Item=LCOVRecord>>(&mut self, records: I) -> Result<(), MergeError> {
+        self.input = None;
+        for (i, record) in records.into_iter().enumerate() {
+            self.line = Some(i as u32 + 1);
+            self.process_record(&record).map_err(|err| self.error_context(err))?;
+        }
+        Ok(())
+    }
+    /// Merge the report which has already been merged, the paths of the source files are rewritten by the rules.
+    /// The error is returned as `MergeError::Context` of the source file.
+    pub fn merge_report(&mut self, report: &Report) -> Result<(), MergeError> {
+        self.input = None;
+        self.line = None;
+        for (source_name, file) in report.files().iter() {
+            let source_name = self.rewrite_path(source_name);
+            if self.is_excluded(&source_name) {
+                continue;
+            }
+            self.merge_source_file(&source_name, file)
+                .map_err(|err| self.source_file_error(&source_name, err.into()))?;
         }
-        Ok(Report::new(self.files.clone()))
+        Ok(())
     }
-    fn process_file<T: AsRef<Path>>(&mut self, file: T) -> Result<(), MergeError> {
-        let mut parser = LCOVParser::from_file(file)?;
+    /// Returns the report of the sources merged so far
+    pub fn report(&self) -> Report {
+        Report::new(self.files.clone())
+    }
+    fn process_parser<R: Read>(&mut self, mut parser: LCOVParser<R>, path: Option<&Path>) -> Result<(), MergeError> {
+        parser.set_mode(self.parse_mode);
+        self.input = path.map(|path| path.to_path_buf());
+        let first_warning = self.warnings.len();
 
         loop {
-            let result = parser.next()?;
+            let result = parser.next();
+            self.line = Some(parser.line());
+            let result = match result {
+                Ok(result) => result,
+                Err(err) => return Err(self.error_context(err.into()))
+            };
 
             if result.is_none() {
                 break;
             }
             let record = result.unwrap();
+            self.process_record(&record).map_err(|err| self.error_context(err))?;
+        }
+        self.finish_input().map_err(|err| self.error_context(err))?;
 
-            match record {
-                LCOVRecord::TestName(ref name) => self.on_test_name(name),
-                LCOVRecord::SourceFile(ref name) => self.on_source_file(name),
-                LCOVRecord::Data(ref data) => self.on_data(data)?,
-                LCOVRecord::FunctionName(ref func_name) => self.on_func_name(func_name)?,
-                LCOVRecord::FunctionData(ref func_data) => self.on_func_data(func_data)?,
-                LCOVRecord::BranchData(ref branch_data) => self.on_branch_data(branch_data)?,
-                LCOVRecord::EndOfRecord => self.on_end_of_record()?,
-                _ => { continue; }
-            };
+        for error in parser.warnings().iter() {
+            self.warnings.push(MergeWarning {
+                path: path.map(|path| path.to_path_buf()),
+                error: error.clone()
+            });
+        }
+        self.warnings[first_warning..].sort_by_key(|warning| warning.error.line);
+        Ok(())
+    }
+    /// Merge the record left without end_of_record at the end of the input, so that no state is carried into the next input
+    fn finish_input(&mut self) -> Result<(), MergeError> {
+        let has_records = self.tests.iter().any(|(_, test)| {
+            !test.lines().is_empty() || !test.functions().is_empty() || !test.branches().is_empty()
+        });
+        if self.source_name.is_some() || has_records {
+            self.on_end_of_record()?;
         }
+        self.test_name = None;
+        self.source_name = None;
+        self.excluded = false;
+        self.tests = Tests::new();
+        Ok(())
+    }
+    fn process_record(&mut self, record: &LCOVRecord) -> Result<(), MergeError> {
+        if self.excluded {
+            match *record {
+                LCOVRecord::TestName(_) | LCOVRecord::SourceFile(_) | LCOVRecord::EndOfRecord => {},
+                _ => return Ok(())
+            }
+        }
+        match *record {
+            LCOVRecord::TestName(ref name) => self.on_test_name(name),
+            LCOVRecord::SourceFile(ref name) => self.on_source_file(name),
+            LCOVRecord::Data(ref data) => self.on_data(data)?,
+            LCOVRecord::FunctionName(ref func_name) => self.on_func_name(func_name)?,
+            LCOVRecord::FunctionData(ref func_data) => self.on_func_data(func_data)?,
+            LCOVRecord::FunctionLine(ref func_line) => self.on_func_line(func_line)?,
+            LCOVRecord::FunctionAlias(ref func_alias) => self.on_func_alias(func_alias)?,
+            LCOVRecord::BranchData(ref branch_data) => self.on_branch_data(branch_data)?,
+            LCOVRecord::EndOfRecord => self.on_end_of_record()?,
+            _ => {}
+        };
         Ok(())
     }
 
     fn on_test_name(&mut self, test_name: &Option<String>) {
         self.test_name = match test_name {
-            &Some(ref name) => Some(name.clone()),
-            &None => Some(String::new())
+            Some(name) => Some(name.clone()),
+            None => Some(String::new())
         };
         let current_test_name = self.test_name.as_ref().unwrap();
         self.tests.merge(current_test_name);
     }
-    fn on_source_file(&mut self, source_name: &String) {
-        self.source_name = Some(source_name.clone());
+    fn on_source_file(&mut self, source_name: &str) {
+        let source_name = self.rewrite_path(source_name);
+        self.excluded = self.is_excluded(&source_name);
+        self.source_name = Some(source_name);
     }
-    fn on_data(&mut self, line_data: &LineData) -> MergeResult<ChecksumError> {
-        if self.test_name.is_some() {
-            let test_name = self.test_name.as_ref().unwrap();
-            self.tests.try_merge((test_name, line_data))?;
+    fn on_data(&mut self, line_data: &LineData) -> MergeResult<TestError> {
+        let test_name = match self.test_name {
+            Some(ref test_name) => test_name.clone(),
+            None => return Ok(())
+        };
+        match self.tests.try_merge((&test_name, line_data)) {
+            Err(_) if self.merge_policy != MergePolicy::Fail => {
+                let mut line = Test::new();
+                line.try_merge(line_data)?;
+
+                let policy = self.merge_policy;
+                let current = self.tests.get_mut(&test_name).unwrap();
+                let errors = resolve_lines(policy, current, &mut line);
+                current.try_merge(&line)?;
+
+                let source_name = self.source_name.clone().unwrap_or_default();
+                self.add_conflicts(&source_name, &test_name, errors);
+                Ok(())
+            },
+            result => Ok(result?)
         }
-        Ok(())
     }
-    fn on_func_name(&mut self, func_name: &FunctionName) -> MergeResult<FunctionError> {
+    fn on_func_name(&mut self, func_name: &FunctionName) -> MergeResult<TestError> {
         if self.test_name.is_none() {
             return Ok(());
         }
 
         let test_name = self.test_name.as_ref().unwrap();
-        self.tests.try_merge((test_name, func_name))?;
-        Ok(())
+        let result = self.tests.try_merge((test_name, func_name));
+        self.skip_conflict(result)
     }
-    fn on_func_data(&mut self, func_data: &FunctionDataRecord) -> MergeResult<FunctionError> {
+    fn on_func_data(&mut self, func_data: &FunctionDataRecord) -> MergeResult<TestError> {
         if self.test_name.is_none() {
             return Ok(());
         }
 
         let test_name = self.test_name.as_ref().unwrap();
-        self.tests.try_merge((test_name, func_data))?;
-        Ok(())
+        let result = self.tests.try_merge((test_name, func_data));
+        self.skip_conflict(result)
     }
-    fn on_branch_data(&mut self, branch_data: &BranchDataRecord) -> MergeResult<BranchError> {
+    fn on_func_line(&mut self, func_line: &FunctionLine) -> MergeResult<TestError> {
         if self.test_name.is_none() {
             return Ok(());
         }
+
         let test_name = self.test_name.as_ref().unwrap();
-        self.tests.try_merge((test_name, branch_data))?;
-        Ok(())
+        let result = self.tests.try_merge((test_name, func_line));
+        self.skip_conflict(result)
     }
-    fn on_end_of_record(&mut self) -> MergeResult<TestError> {
-        let source_name = self.source_name.as_ref().unwrap();
-        let file = File::new(self.tests.clone());
+    fn on_func_alias(&mut self, func_alias: &FunctionAlias) -> MergeResult<TestError> {
+        if self.test_name.is_none() {
+            return Ok(());
+        }
 
-        self.files.try_merge((source_name, &file))?;
+        let test_name = self.test_name.as_ref().unwrap();
+        let result = self.tests.try_merge((test_name, func_alias));
+        self.skip_conflict(result)
+    }
+    fn on_branch_data(&mut self, branch_data: &BranchDataRecord) -> MergeResult<TestError> {
+        if self.test_name.is_none() {
+            return Ok(());
+        }
+        let test_name = self.test_name.as_ref().unwrap();
+        let result = self.tests.try_merge((test_name, branch_data));
+        self.skip_conflict(result)
+    }
+    fn on_end_of_record(&mut self) -> Result<(), MergeError> {
+        let source_name = match self.source_name {
+            Some(ref source_name) => source_name.clone(),
+            None => return self.on_missing_source_file()
+        };
+        if !self.excluded {
+            let file = File::new(self.tests.clone());
+
+            if self.overflow_warning {
+                self.check_overflow(&source_name, file.tests());
+            }
+            self.merge_source_file(&source_name, &file)?;
+            if let Some(line) = self.line {
+                let input = self.input.clone();
+                self.record_locations.entry(source_name).or_insert((input, line));
+            }
+        }
+        self.source_name = None;
+        self.tests = Tests::new();
+        Ok(())
+    }
+    /// The record without SF fails in the strict mode, and its tests are dropped with a warning in the lenient mode
+    fn on_missing_source_file(&mut self) -> Result<(), MergeError> {
+        let error = RecordParseError {
+            line: self.line.unwrap_or_default(),
+            column: 1,
+            message: "end_of_record without a source file (SF)".to_string()
+        };
+        if self.parse_mode == ParseMode::Strict {
+            return Err(MergeError::RecordParse(error));
+        }
+        self.warnings.push(MergeWarning {
+            path: self.input.clone(),
+            error
+        });
         self.tests = Tests::new();
         Ok(())
     }
+    /// Returns the empty merger which has the same settings
+    #[cfg(feature = "rayon")]
+    fn partial(&self) -> ReportMerger {
+        let mut partial = ReportMerger::new();
+        partial.parse_mode = self.parse_mode;
+        partial.overflow_warning = self.overflow_warning;
+        partial.merge_policy = self.merge_policy;
+        partial.path_rules = self.path_rules.clone();
+        #[cfg(feature = "filter")]
+        {
+            partial.file_filter = self.file_filter.clone();
+        }
+        partial
+    }
+    /// Merge the partial report of the files which follow the files merged so far
+    #[cfg(feature = "rayon")]
+    fn append(&mut self, partial: ReportMerger) -> Result<(), MergeError> {
+        if self.overflow_warning {
+            for (source_name, file) in partial.files.iter() {
+                self.check_overflow(source_name, file.tests());
+            }
+        }
+        self.conflicts.extend(partial.conflicts);
+        for (source_name, file) in partial.files.iter() {
+            let (input, line) = match partial.record_locations.get(source_name) {
+                Some(&(ref input, line)) => (input.clone(), Some(line)),
+                None => (None, None)
+            };
+            self.input = input;
+            self.line = line;
+            self.merge_source_file(source_name, file)
+                .map_err(|err| self.source_file_error(source_name, err.into()))?;
+        }
+        for (source_name, location) in partial.record_locations {
+            self.record_locations.entry(source_name).or_insert(location);
+        }
+        self.warnings.extend(partial.warnings);
+        self.overflows.extend(partial.overflows);
+        Ok(())
+    }
+    /// Merge the file into the report, the conflicting lines are resolved by the policy before merging
+    fn merge_source_file(&mut self, source_name: &SourceFile, file: &File) -> MergeResult<TestError> {
+        if self.merge_policy == MergePolicy::Fail {
+            return self.files.try_merge((source_name, file));
+        }
+        let policy = self.merge_policy;
+        let mut tests = file.tests().clone();
+        let mut conflicts = vec![];
+
+        if let Some(current_file) = self.files.get_mut(source_name) {
+            let test_names: Vec<_> = tests.iter().map(|(test_name, _)| test_name.clone()).collect();
+
+            for test_name in test_names {
+                if let (Some(current), Some(test)) = (current_file.get_test_mut(&test_name), tests.get_mut(&test_name)) {
+                    let errors = resolve_lines(policy, current, test);
+                    conflicts.push((test_name, errors));
+                }
+            }
+        }
+        self.files.try_merge((source_name, &File::new(tests)))?;
+
+        for (test_name, errors) in conflicts {
+            self.add_conflicts(source_name, &test_name, errors);
+        }
+        Ok(())
+    }
+    /// Returns Ok if the error of the record is resolved by skipping the record
+    fn skip_conflict<E: Into<TestError>>(&mut self, result: MergeResult<E>) -> MergeResult<TestError> {
+        let error = match result {
+            Ok(_) => return Ok(()),
+            Err(error) => error.into()
+        };
+        if self.merge_policy == MergePolicy::Fail {
+            return Err(error);
+        }
+        let context = self.context(self.source_name.as_ref(), self.test_name.as_ref());
+        self.conflicts.push(MergeConflict { context, error });
+        Ok(())
+    }
+    fn add_conflicts(&mut self, source_name: &SourceFile, test_name: &TestName, errors: Vec<ChecksumError>) {
+        let context = self.context(Some(source_name), Some(test_name));
+        self.conflicts.extend(errors.into_iter().map(|error| MergeConflict {
+            context: context.clone(),
+            error: TestError::Checksum(error)
+        }));
+    }
+    /// Returns the tracefile and the line of the record being merged, with the source file and the test
+    fn context(&self, source_name: Option<&SourceFile>, test_name: Option<&TestName>) -> MergeContext {
+        MergeContext {
+            path: self.input.clone(),
+            line: self.line,
+            source_file: source_name.cloned(),
+            test_name: test_name.filter(|test_name| !test_name.is_empty()).cloned()
+        }
+    }
+    /// Returns the error with the tracefile, the line, and the source file and the test read last
+    fn error_context(&self, err: MergeError) -> MergeError {
+        let context = self.context(self.source_name.as_ref(), self.test_name.as_ref());
+        MergeError::Context(context, Box::new(err))
+    }
+    fn source_file_error(&self, source_name: &SourceFile, err: MergeError) -> MergeError {
+        MergeError::Context(self.context(Some(source_name), None), Box::new(err))
+    }
+    fn rewrite_path(&self, source_name: &str) -> SourceFile {
+        self.path_rules.iter().fold(source_name.to_string(), |path, rule| rule.apply(&path))
+    }
+    #[cfg(feature = "filter")]
+    fn is_excluded(&self, source_name: &str) -> bool {
+        self.file_filter.as_ref().map(|filter| !filter.is_match(source_name)).unwrap_or(false)
+    }
+    #[cfg(not(feature = "filter"))]
+    fn is_excluded(&self, _source_name: &str) -> bool {
+        false
+    }
+    fn check_overflow(&mut self, source_name: &SourceFile, tests: &Tests) {
+        let current_file = match self.files.get(source_name) {
+            Some(file) => file,
+            None => return
+        };
+        let overflowed = |current: &ExecutionCount, other: &ExecutionCount| current.checked_add(*other).is_none();
+
+        for (test_name, test) in tests.iter() {
+            let current_test = match current_file.get_test(test_name) {
+                Some(test) => test,
+                None => continue
+            };
+            let mut counters = vec![];
+
+            for (line_number, line) in test.lines().iter() {
+                if let Some(current) = current_test.lines().get(line_number) {
+                    if overflowed(current.execution_count(), line.execution_count()) {
+                        counters.push(OverflowCounter::Line(*line_number));
+                    }
+                }
+            }
+            for (name, function) in test.functions().iter() {
+                if let Some(current) = current_test.functions().get(name) {
+                    if overflowed(current.execution_count(), function.execution_count()) {
+                        counters.push(OverflowCounter::Function(name.clone()));
+                    }
+                }
+            }
+            for (line_number, blocks) in test.branches().iter() {
+                for (unit, branch) in blocks.iter() {
+                    let current = current_test.branches().get(line_number)
+                        .and_then(|blocks| blocks.get(unit))
+                        .and_then(|current| current.execution_count());
+
+                    if let (Some(current), Some(other)) = (current, branch.execution_count()) {
+                        if overflowed(current, other) {
+                            counters.push(OverflowCounter::Branch(*line_number, unit.clone()));
+                        }
+                    }
+                }
+            }
+            self.overflows.extend(counters.into_iter().map(|counter| CountOverflow {
+                source_file: source_name.clone(),
+                test_name: test_name.clone(),
+                counter
+            }));
+        }
+    }
+}
+
+
+/// Resolve the lines whose checksums conflict between the tests by the policy, and returns the conflicts
+fn resolve_lines(policy: MergePolicy, current: &mut Test, other: &mut Test) -> Vec<ChecksumError> {
+    let errors: Vec<ChecksumError> = other.lines().iter()
+        .filter_map(|(line_number, line)| {
+            let current_line = current.lines().get(line_number)?;
+            match (current_line.checksum(), line.checksum()) {
+                (Some(checksum), Some(other_checksum)) if checksum != other_checksum => {
+                    Some(ChecksumError::Mismatch(MergeLine::from(current_line), MergeLine::from(line)))
+                },
+                _ => None
+            }
+        })
+        .collect();
+    let conflicting: BTreeSet<LineNumber> = errors.iter()
+        .map(|ChecksumError::Mismatch(line, _)| *line.line())
+        .collect();
+
+    match policy {
+        MergePolicy::Fail => {},
+        MergePolicy::KeepFirst => other.retain_lines(|line_number| !conflicting.contains(line_number)),
+        MergePolicy::KeepLast => current.retain_lines(|line_number| !conflicting.contains(line_number)),
+        MergePolicy::DropConflictingLine => {
+            current.retain_lines(|line_number| !conflicting.contains(line_number));
+            other.retain_lines(|line_number| !conflicting.contains(line_number));
+        },
+        MergePolicy::IgnoreChecksums => {
+            other.set_checksums(|line_number| {
+                if !conflicting.contains(line_number) {
+                    return None;
+                }
+                current.lines().get(line_number).and_then(|line| line.checksum()).cloned()
+            });
+        }
+    }
+    errors
 }
 
 
 #[cfg(test)]
 mod tests {
     use merger::*;
-    use merger::ops:: { MergeError, TestError, ChecksumError, MergeLine };
+    use merger::ops:: { MergeError, MergePolicy, TestError, ChecksumError, FunctionError, MergeLine, OverflowCounter };
+    use report::branch:: { BranchUnit };
+    #[cfg(feature = "filter")]
+    use report::filter:: { FileFilter };
+    use record:: { LCOVRecord };
+    use parser:: { LCOVParser, ParseMode, FromFile };
     use report::summary::{ Summary };
+    use std::fs;
+    use std::io::Read;
 
     #[test]
     fn merge_checksum() {
@@ -176,11 +696,229 @@ mod tests {
         check_merged_report(report2);
     }
 
+    #[test]
+    fn merge_function_alias() {
+        let report = {
+            let report_path = "tests/fixtures/merged/function_alias/fixture.info";
+
+            let mut parse = ReportMerger::new();
+            parse.merge(&[ report_path, report_path ]).unwrap()
+        };
+
+        let file = report.get("/fixture.cpp").unwrap();
+        let test = file.get_test(&"example".to_string()).unwrap();
+        let functions = test.functions();
+        let function = functions.get(&"_ZN3FooC2Ev".to_string()).unwrap();
+
+        assert_eq!(function.line_number(), &3);
+        assert_eq!(function.end_line_number(), Some(&6));
+        assert_eq!(function.execution_count(), &2);
+        assert_eq!(functions.aliases(&"_ZN3FooC1Ev".to_string()).len(), 2);
+        assert_eq!(functions.aliases(&"_ZN3Foo3barEv".to_string()).len(), 1);
+    }
+
+    #[test]
+    fn merge_not_executed_branch() {
+        let report_path = "tests/fixtures/merged/not_executed_branch/fixture.info";
+        let report = {
+            let mut parse = ReportMerger::new();
+            parse.merge(&[ report_path ]).unwrap()
+        };
+        let readed_file_content = {
+            let mut output = String::new();
+            let mut f = fs::File::open(report_path).unwrap();
+            let _ = f.read_to_string(&mut output);
+            output
+        };
+        assert_eq!(report.to_string(), readed_file_content);
+    }
+
+    #[test]
+    fn merge_sources_incrementally() {
+        let report_path1 = "tests/fixtures/merged/eq_checksum/fixture1.info";
+        let report_path2 = "tests/fixtures/merged/eq_checksum/fixture2.info";
+
+        let expected = {
+            let mut parse = ReportMerger::new();
+            parse.merge(&[ report_path1, report_path2 ]).unwrap()
+        };
+
+        let mut parse = ReportMerger::new();
+        parse.merge_reader(fs::File::open(report_path1).unwrap()).unwrap();
+        let records = LCOVParser::from_file(report_path2).unwrap().parse().unwrap();
+        parse.merge_records(records).unwrap();
+        assert_eq!(parse.report().to_string(), expected.to_string());
+
+        let mut parse = ReportMerger::new();
+        parse.merge_file(report_path1).unwrap();
+        let report = {
+            let mut parse = ReportMerger::new();
+            parse.merge(&[ report_path2 ]).unwrap()
+        };
+        parse.merge_report(&report).unwrap();
+        assert_eq!(parse.report().to_string(), expected.to_string());
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn merge_parallel() {
+        let report_paths = [
+            "tests/fixtures/merged/eq_checksum/fixture1.info",
+            "tests/fixtures/merged/eq_checksum/fixture2.info",
+            "tests/fixtures/merged/one_side_checksum/fixture1.info",
+            "tests/fixtures/merged/one_side_checksum/fixture2.info",
+            "tests/fixtures/merged/function_alias/fixture.info"
+        ];
+        let expected = {
+            let mut parse = ReportMerger::new();
+            parse.merge(&report_paths).unwrap()
+        };
+        let report = {
+            let mut parse = ReportMerger::new();
+            parse.merge_parallel(&report_paths).unwrap()
+        };
+        assert_eq!(report.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn merge_without_end_of_record() {
+        let report_paths = [
+            "tests/fixtures/merged/without_end_of_record/fixture1.info",
+            "tests/fixtures/merged/without_end_of_record/fixture2.info"
+        ];
+        let report = ReportMerger::new().merge(&report_paths).unwrap();
+
+        let test = report.get("/a.c").unwrap().get_test(&"example".to_string()).unwrap();
+        assert_eq!(test.lines().get(&1).unwrap().execution_count(), &1);
+        assert_eq!(test.lines().get(&2).unwrap().execution_count(), &1);
+
+        #[cfg(feature = "rayon")]
+        assert_eq!(ReportMerger::new().merge_parallel(&report_paths).unwrap().to_string(), report.to_string());
+
+        let mut parse = ReportMerger::new();
+        let err = parse.merge_reader("TN:example\nDA:1,1\n".as_bytes()).unwrap_err();
+        assert!(matches!(err.root_cause(), MergeError::RecordParse(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn merge_parallel_error() {
+        let report_path1 = "tests/fixtures/merged/ne_checksum/fixture1.info";
+        let report_path2 = "tests/fixtures/merged/ne_checksum/fixture2.info";
+        let report_path3 = "tests/fixtures/merged/unknown_records/fixture.info";
+
+        let mut parse = ReportMerger::new();
+        let result = parse.merge_parallel(&[ report_path1, report_path2 ]).unwrap_err();
+        assert!(match result {
+            MergeError::Context(ref context, _) => {
+                context.path.as_ref().and_then(|path| path.to_str()) == Some(report_path2) && matches!(result.root_cause(), MergeError::Process(_))
+            },
+            _ => false
+        });
+
+        let mut parse = ReportMerger::new();
+        let result = parse.merge_parallel(&[ report_path1, report_path3 ]).unwrap_err();
+        assert!(match result {
+            MergeError::Context(ref context, _) => {
+                context.path.as_ref().and_then(|path| path.to_str()) == Some(report_path3) && matches!(result.root_cause(), MergeError::RecordParse(_))
+            },
+            _ => false
+        });
+    }
+
+    #[test]
+    fn merge_count_overflow() {
+        let report_path = "tests/fixtures/merged/count_overflow/fixture.info";
+
+        let mut parse = ReportMerger::new();
+        parse.set_overflow_warning(true);
+        let report = parse.merge(&[ report_path, report_path ]).unwrap();
+
+        let file = report.get("/fixture.c").unwrap();
+        let test = file.get_test(&"example".to_string()).unwrap();
+        assert_eq!(test.lines().get(&4).unwrap().execution_count(), &u64::MAX);
+        assert_eq!(test.lines().get(&5).unwrap().execution_count(), &2);
+
+        let counters: Vec<OverflowCounter> = parse.overflows().iter()
+            .map(|overflow| overflow.counter.clone())
+            .collect();
+        assert_eq!(counters, vec![
+            OverflowCounter::Line(4),
+            OverflowCounter::Function("main".to_string()),
+            OverflowCounter::Branch(4, BranchUnit::new(0, 0))
+        ]);
+        assert_eq!(parse.overflows()[0].to_string(), "Execution count saturated: source file: /fixture.c, test: example, line: 4");
+
+        let mut parse = ReportMerger::new();
+        parse.merge(&[ report_path, report_path ]).unwrap();
+        assert!(parse.overflows().is_empty());
+    }
+
+    #[test]
+    fn merge_unknown_records() {
+        let report_path = "tests/fixtures/merged/unknown_records/fixture.info";
+
+        let result = {
+            let mut parse = ReportMerger::new();
+            parse.merge(&[ report_path ])
+        };
+        assert!(match result.as_ref().map_err(|err| err.root_cause()) {
+            Err(MergeError::RecordParse(err)) => err.line == 1,
+            _ => false
+        });
+
+        let mut parse = ReportMerger::new();
+        parse.set_parse_mode(ParseMode::Lenient);
+        let report = parse.merge(&[ report_path ]).unwrap();
+
+        let file = report.get("/fixture.c").unwrap();
+        let test = file.get_test(&"example".to_string()).unwrap();
+        assert_eq!(test.lines().len(), 2);
+
+        let warning_lines: Vec<u32> = parse.warnings().iter()
+            .map(|warning| warning.error.line)
+            .collect();
+        assert_eq!(warning_lines, vec![1, 4, 6]);
+        assert_eq!(parse.warnings()[0].path, Some(report_path.into()));
+    }
+
+    #[test]
+    fn merge_end_of_record_without_source_file() {
+        let mut parse = ReportMerger::new();
+        let err = parse.merge_records(vec![ LCOVRecord::EndOfRecord ]).unwrap_err();
+        assert!(match err.root_cause() {
+            MergeError::RecordParse(err) => err.line == 1,
+            _ => false
+        });
+
+        let mut parse = ReportMerger::new();
+        let err = parse.merge_reader("TN:test\nSF\nDA:1,1\nend_of_record\n".as_bytes()).unwrap_err();
+        assert!(matches!(err.root_cause(), MergeError::RecordParse(_)));
+    }
+
+    #[test]
+    fn merge_end_of_record_without_source_file_lenient() {
+        let mut parse = ReportMerger::new();
+        parse.set_parse_mode(ParseMode::Lenient);
+        parse.merge_reader("TN:test\nSF\nDA:1,1\nend_of_record\nSF:/a.c\nDA:2,1\nend_of_record\n".as_bytes()).unwrap();
+
+        let report = parse.report();
+        assert_eq!(report.len(), 1);
+        let test = report.get("/a.c").unwrap().get_test(&"test".to_string()).unwrap();
+        assert_eq!(test.lines().len(), 1);
+        assert!(test.lines().contains_key(&2));
+
+        let warning_lines: Vec<u32> = parse.warnings().iter()
+            .map(|warning| warning.error.line)
+            .collect();
+        assert_eq!(warning_lines, vec![2, 4]);
+    }
+
     #[test]
     fn merge_checksum_error() {
+        let report_path2 = "tests/fixtures/merged/ne_checksum/fixture2.info";
         let result = {
             let report_path1 = "tests/fixtures/merged/ne_checksum/fixture1.info";
-            let report_path2 = "tests/fixtures/merged/ne_checksum/fixture2.info";
 
             let mut parse = ReportMerger::new();
             parse.merge(&[ report_path1, report_path2 ]).unwrap_err()
@@ -192,14 +930,178 @@ mod tests {
         );
         let test_error = TestError::from(checksum_error);
 
+        assert!(match result {
+            MergeError::Context(ref context, _) => {
+                context.path == Some(report_path2.into()) &&
+                    context.line == Some(10) &&
+                    context.source_file == Some("/fixture1.c".to_string()) &&
+                    context.test_name == Some("example".to_string())
+            },
+            _ => false
+        });
+        assert_eq!(result.to_string(), concat!(
+            "tests/fixtures/merged/ne_checksum/fixture2.info:10, source file: /fixture1.c, test: example: ",
+            "Source code checksums do not match: line: 4, left: y7GE3Y4FyXCeXcrtqgSVzw, right: invalid"
+        ));
+
         // see pull request
         // https://github.com/rust-lang/rust/pull/34192
-        assert!(match result {
+        assert!(match result.root_cause() {
             MergeError::Process(err) => {
                 println!("raised error: {}", err);
-                err == test_error
+                *err == test_error
             },
             _ => false
         })
     }
+
+    #[test]
+    fn merge_with_path_rules() {
+        let report = {
+            let report_path1 = "tests/fixtures/merged/path_rules/agent1.info";
+            let report_path2 = "tests/fixtures/merged/path_rules/agent2.info";
+
+            let mut parse = ReportMerger::new();
+            parse.add_path_rule(PathRule::Normalize);
+            parse.add_path_rule(PathRule::StripPrefix("/home/ci/agent-3/".to_string()));
+            parse.add_path_rule(PathRule::RelativeTo("/builds/x".into()));
+            parse.merge(&[ report_path1, report_path2 ]).unwrap()
+        };
+
+        assert_eq!(report.len(), 1);
+        let file = report.get("src/foo.c").unwrap();
+        let test = file.get_test(&"example".to_string()).unwrap();
+        assert_eq!(test.lines().get(&1).unwrap().execution_count(), &3);
+        assert_eq!(test.lines().get(&2).unwrap().execution_count(), &3);
+    }
+
+    #[test]
+    fn merge_report_with_path_rules() {
+        let report = merge_files(&[ "tests/fixtures/merged/path_rules/agent1.info" ]).unwrap();
+
+        let mut parse = ReportMerger::new();
+        parse.add_path_rule(PathRule::CaseFold);
+        parse.merge_report(&report).unwrap();
+
+        assert!(parse.report().get("/home/ci/agent-3/src/foo.c").is_some());
+
+        let mut parse = ReportMerger::new();
+        parse.add_path_rule(PathRule::StripPrefix("/home/ci/agent-3/".to_string()));
+        parse.merge_report(&report).unwrap();
+
+        assert!(parse.report().get("src/foo.c").is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "filter")]
+    fn merge_with_file_filter() {
+        let mut filter = FileFilter::new();
+        filter.exclude("/func*.c").unwrap();
+
+        let mut parse = ReportMerger::new();
+        parse.set_file_filter(filter.clone());
+        let report = parse.merge(&[ "tests/fixtures/merged/eq_checksum/fixture1.info" ]).unwrap();
+
+        let files: Vec<_> = report.files().iter().map(|(source_name, _)| source_name.as_str()).collect();
+        assert_eq!(files, vec![ "/fixture1.c", "/fixture2.c" ]);
+
+        let unfiltered = merge_files(&[ "tests/fixtures/merged/eq_checksum/fixture1.info" ]).unwrap();
+        assert_eq!(unfiltered.filter(&filter).to_string(), report.to_string());
+
+        let mut parse = ReportMerger::new();
+        parse.set_file_filter(filter);
+        parse.merge_report(&unfiltered).unwrap();
+        assert_eq!(parse.report().to_string(), report.to_string());
+    }
+
+    #[test]
+    fn merge_with_policy() {
+        let report_path1 = "tests/fixtures/merged/ne_checksum/fixture1.info";
+        let report_path2 = "tests/fixtures/merged/ne_checksum/fixture2.info";
+
+        let merge = |policy| {
+            let mut parse = ReportMerger::new();
+            parse.set_merge_policy(policy);
+            let report = parse.merge(&[ report_path1, report_path2 ]).unwrap();
+            assert_eq!(parse.conflicts().len(), 2);
+
+            let test = report.get("/fixture1.c").unwrap().get_test(&"example".to_string()).unwrap();
+            test.lines().get(&4).map(|line| (*line.execution_count(), line.checksum().unwrap().clone()))
+        };
+        assert_eq!(merge(MergePolicy::KeepFirst), Some((1, "y7GE3Y4FyXCeXcrtqgSVzw".to_string())));
+        assert_eq!(merge(MergePolicy::KeepLast), Some((1, "invalid".to_string())));
+        assert_eq!(merge(MergePolicy::DropConflictingLine), None);
+        assert_eq!(merge(MergePolicy::IgnoreChecksums), Some((2, "y7GE3Y4FyXCeXcrtqgSVzw".to_string())));
+    }
+
+    #[test]
+    fn merge_conflicts() {
+        let report_path1 = "tests/fixtures/merged/ne_checksum/fixture1.info";
+        let report_path2 = "tests/fixtures/merged/ne_checksum/fixture2.info";
+
+        let mut parse = ReportMerger::new();
+        parse.set_merge_policy(MergePolicy::KeepFirst);
+        parse.merge(&[ report_path1, report_path2 ]).unwrap();
+
+        let conflict = &parse.conflicts()[0];
+        assert_eq!(conflict.context.path, Some(report_path2.into()));
+        assert_eq!(conflict.context.line, Some(10));
+        assert_eq!(conflict.context.source_file, Some("/fixture1.c".to_string()));
+        assert_eq!(conflict.line_number(), Some(4));
+        assert_eq!(conflict.to_string(), concat!(
+            "tests/fixtures/merged/ne_checksum/fixture2.info:10, source file: /fixture1.c, test: example: ",
+            "Source code checksums do not match: line: 4, left: y7GE3Y4FyXCeXcrtqgSVzw, right: invalid"
+        ));
+
+        let mut parse = ReportMerger::new();
+        parse.set_merge_policy(MergePolicy::KeepLast);
+        parse.merge_reader("TN:\nSF:/a.c\nDA:1,1,abc\nDA:1,2,xyz\nFNA:1,1,foo\nend_of_record\n".as_bytes()).unwrap();
+
+        let report = parse.report();
+        let test = report.get("/a.c").unwrap().get_test(&"".to_string()).unwrap();
+        assert_eq!(test.lines().get(&1).unwrap().execution_count(), &2);
+        assert_eq!(parse.conflicts()[0].context.path, None);
+        assert_eq!(parse.conflicts()[0].context.line, Some(4));
+        assert_eq!(parse.conflicts()[1].context.line, Some(5));
+        assert_eq!(parse.conflicts()[1].error, TestError::Function(FunctionError::UnknownIndex(1)));
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn merge_parallel_with_policy() {
+        let report_paths = [
+            "tests/fixtures/merged/ne_checksum/fixture1.info",
+            "tests/fixtures/merged/ne_checksum/fixture2.info"
+        ];
+        let mut expected = ReportMerger::new();
+        expected.set_merge_policy(MergePolicy::KeepLast);
+        let expected_report = expected.merge(&report_paths).unwrap();
+
+        let mut parse = ReportMerger::new();
+        parse.set_merge_policy(MergePolicy::KeepLast);
+        let report = parse.merge_parallel(&report_paths).unwrap();
+
+        assert_eq!(report.to_string(), expected_report.to_string());
+        assert_eq!(parse.conflicts(), expected.conflicts());
+    }
+
+    #[test]
+    fn merge_error_context() {
+        let mut parse = ReportMerger::new();
+        let result = parse.merge(&[ "tests/fixtures/merged/not_found.info" ]).unwrap_err();
+        assert!(match result {
+            MergeError::Context(ref context, ref err) => {
+                context.path == Some("tests/fixtures/merged/not_found.info".into()) && matches!(**err, MergeError::IO(_))
+            },
+            _ => false
+        });
+
+        let report1 = merge_files(&[ "tests/fixtures/merged/ne_checksum/fixture1.info" ]).unwrap();
+        let report2 = merge_files(&[ "tests/fixtures/merged/ne_checksum/fixture2.info" ]).unwrap();
+
+        let mut parse = ReportMerger::new();
+        parse.merge_report(&report1).unwrap();
+        let result = parse.merge_report(&report2).unwrap_err();
+        assert!(result.to_string().starts_with("source file: /fixture1.c: Source code checksums do not match"));
+    }
 }
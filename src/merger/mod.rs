@@ -6,10 +6,16 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
+#[allow(clippy::module_inception)]
 mod merger;
+mod path;
 pub mod ops;
 
 pub use merger::merger:: { ReportMerger };
+pub use merger::path:: { PathRule };
 
 use std::path::Path;
 use report::*;
@@ -41,3 +47,24 @@ pub fn merge_files<T: AsRef<Path>>(files: &[T]) -> Result<Report, MergeError> {
     let mut merger = ReportMerger::new();
     merger.merge(files)
 }
+
+/// Merge reports in parallel, the result is identical to `merge_files`
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser::merge_files_parallel;
+///
+/// let trace_files = [
+///    "tests/fixtures/merged/eq_checksum/fixture1.info",
+///    "tests/fixtures/merged/eq_checksum/fixture2.info"
+/// ];
+/// let report = merge_files_parallel(&trace_files).unwrap();
+///
+/// assert!(report.get("/fixture1.c").is_some());
+/// ```
+#[cfg(feature = "rayon")]
+pub fn merge_files_parallel<T: AsRef<Path> + Sync>(files: &[T]) -> Result<Report, MergeError> {
+    let mut merger = ReportMerger::new();
+    merger.merge_parallel(files)
+}
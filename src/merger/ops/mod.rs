@@ -9,25 +9,28 @@
 use std::result::Result;
 use std::convert::From;
 use std::io:: { Error as IOError};
+use std::path:: { PathBuf };
 use std::fmt;
+use std::error:: { Error };
 use parser:: { ParseError, RecordParseError };
 use record:: { BranchData };
 use report::line:: { Line };
 use report::branch:: { Branch };
-use report::attribute:: { LineNumber, FunctionName, CheckSum };
+use report::branch:: { BranchUnit };
+use report::attribute:: { LineNumber, FunctionName, CheckSum, SourceFile, TestName };
 
 pub type MergeResult<E> = Result<(), E>;
 
 pub trait Merge<Rhs=Self> {
-    fn merge(&mut self, Rhs);
+    fn merge(&mut self, other: Rhs);
 }
 
 pub trait TryMerge<Rhs=Self> {
     type Err;
-    fn try_merge(&mut self, Rhs) -> MergeResult<Self::Err>;
+    fn try_merge(&mut self, other: Rhs) -> MergeResult<Self::Err>;
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum ChecksumError {
     Mismatch(MergeLine, MergeLine)
 }
@@ -35,7 +38,7 @@ pub enum ChecksumError {
 impl fmt::Display for ChecksumError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &ChecksumError::Mismatch(ref line1, ref line2) => {
+            ChecksumError::Mismatch(line1, line2) => {
                 write!(f, "Source code checksums do not match: line: {}, left: {}, right: {}",
                     line1.line(),
                     line1.checksum(),
@@ -45,7 +48,7 @@ impl fmt::Display for ChecksumError {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct MergeLine {
     line: LineNumber,
     checksum: Option<CheckSum>
@@ -55,7 +58,7 @@ impl MergeLine {
     pub fn new(line_number: LineNumber, checksum: Option<CheckSum>) -> MergeLine {
         MergeLine {
             line: line_number,
-            checksum: checksum
+            checksum
         }
     }
     pub fn line(&self) -> &LineNumber {
@@ -64,18 +67,15 @@ impl MergeLine {
     pub fn checksum(&self) -> &str {
         match self.checksum {
             Some(ref checksum) => checksum.as_str(),
-            None => &""
+            None => ""
         }
     }
 }
 
 impl<'a> From<&'a Line> for MergeLine {
     fn from(line: &'a Line) -> Self {
-        let line_number = line.line_number().clone();
-        let checksum = match line.checksum() {
-            Some(value) => Some(value.clone()),
-            None => None
-        };
+        let line_number = *line.line_number();
+        let checksum = line.checksum().cloned();
         MergeLine::new(line_number, checksum)
     }
 }
@@ -86,22 +86,26 @@ impl fmt::Display for MergeLine {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum FunctionError {
-    Mismatch(FunctionName, FunctionName)
+    Mismatch(FunctionName, FunctionName),
+    UnknownIndex(u32)
 }
 
 impl fmt::Display for FunctionError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &FunctionError::Mismatch(ref func1, ref func2) => {
+            FunctionError::Mismatch(func1, func2) => {
                 write!(f, "Function name mismatch: left = {}, right = {}", func1, func2)
+            },
+            FunctionError::UnknownIndex(index) => {
+                write!(f, "Function index is not defined: {}", index)
             }
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct MergeBranch {
     pub line: LineNumber,
     pub block: u32,
@@ -137,7 +141,7 @@ impl fmt::Display for MergeBranch {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum BranchError {
     Mismatch(MergeBranch, MergeBranch)
 }
@@ -145,14 +149,14 @@ pub enum BranchError {
 impl fmt::Display for BranchError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &BranchError::Mismatch(ref branch1, ref branch2) => {
+            BranchError::Mismatch(branch1, branch2) => {
                 write!(f, "Branch mismatch: left = {}, right = {}", branch1, branch2)
             }
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum TestError {
     Checksum(ChecksumError),
     Function(FunctionError),
@@ -162,9 +166,9 @@ pub enum TestError {
 impl fmt::Display for TestError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &TestError::Checksum(ref err) => write!(f, "{}", err),
-            &TestError::Function(ref err) => write!(f, "{}", err),
-            &TestError::Branch(ref err) => write!(f, "{}", err)
+            TestError::Checksum(err) => write!(f, "{}", err),
+            TestError::Function(err) => write!(f, "{}", err),
+            TestError::Branch(err) => write!(f, "{}", err)
         }
     }
 }
@@ -173,11 +177,155 @@ impl_from_error!(ChecksumError, TestError::Checksum);
 impl_from_error!(FunctionError, TestError::Function);
 impl_from_error!(BranchError, TestError::Branch);
 
+/// Record skipped or read as unknown by the lenient parser.
+/// The path is `None` for the records read by `ReportMerger::merge_reader`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MergeWarning {
+    pub path: Option<PathBuf>,
+    pub error: RecordParseError
+}
+
+impl fmt::Display for MergeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.path {
+            Some(ref path) => write!(f, "{}:{}: {}", path.display(), self.error.line, self.error),
+            None => write!(f, "{}: {}", self.error.line, self.error)
+        }
+    }
+}
+
+/// Counter of the execution count that overflowed
+#[derive(Debug, PartialEq, Clone)]
+pub enum OverflowCounter {
+    Line(LineNumber),
+    Function(FunctionName),
+    Branch(LineNumber, BranchUnit)
+}
+
+/// Execution count saturated at `u64::MAX` while merging
+#[derive(Debug, PartialEq, Clone)]
+pub struct CountOverflow {
+    pub source_file: SourceFile,
+    pub test_name: TestName,
+    pub counter: OverflowCounter
+}
+
+impl fmt::Display for CountOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Execution count saturated: source file: {}, test: {}, ", self.source_file, self.test_name)?;
+        match self.counter {
+            OverflowCounter::Line(ref line) => write!(f, "line: {}", line),
+            OverflowCounter::Function(ref name) => write!(f, "function: {}", name),
+            OverflowCounter::Branch(ref line, ref unit) => write!(f, "branch: {}:{}", line, unit)
+        }
+    }
+}
+
+/// Policy to resolve the conflicts while merging
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+pub enum MergePolicy {
+    /// Fail with `MergeError::Process`
+    #[default]
+    Fail,
+    /// Keep the line merged first, and skip the conflicting line
+    KeepFirst,
+    /// Replace the line merged first with the conflicting line
+    KeepLast,
+    /// Remove the line from the report merged so far, and skip the conflicting line
+    DropConflictingLine,
+    /// Sum the execution counts of the lines, the checksum merged first is kept
+    IgnoreChecksums
+}
+
+/// Conflict resolved by the policy other than `MergePolicy::Fail`.
+/// The context has the tracefile and the line of the record where the conflict was found, the line is `None` for the reports.
+/// The conflicts between the tracefiles merged in parallel have the tracefile and the line of the first record of the source file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MergeConflict {
+    pub context: MergeContext,
+    pub error: TestError
+}
+
+impl MergeConflict {
+    /// Returns the line number in the source file, `None` for the function
+    pub fn line_number(&self) -> Option<LineNumber> {
+        match self.error {
+            TestError::Checksum(ChecksumError::Mismatch(ref line, _)) => Some(*line.line()),
+            TestError::Branch(BranchError::Mismatch(ref branch, _)) => Some(branch.line),
+            TestError::Function(_) => None
+        }
+    }
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.context, self.error)
+    }
+}
+
+/// Where the error was raised while merging.
+/// The source file and the test name are the last `SF` and `TN` read before the error.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct MergeContext {
+    /// Tracefile, `None` for the records and the reports
+    pub path: Option<PathBuf>,
+    /// Line number of the record in the tracefile
+    pub line: Option<u32>,
+    pub source_file: Option<SourceFile>,
+    pub test_name: Option<TestName>
+}
+
+impl fmt::Display for MergeContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut fields = vec![];
+        match (&self.path, self.line) {
+            (Some(path), Some(line)) => fields.push(format!("{}:{}", path.display(), line)),
+            (Some(path), None) => fields.push(format!("{}", path.display())),
+            (None, Some(line)) => fields.push(format!("line: {}", line)),
+            (None, None) => {}
+        }
+        if let Some(ref source_file) = self.source_file {
+            fields.push(format!("source file: {}", source_file));
+        }
+        if let Some(ref test_name) = self.test_name {
+            fields.push(format!("test: {}", test_name));
+        }
+        write!(f, "{}", fields.join(", "))
+    }
+}
+
 #[derive(Debug)]
 pub enum MergeError {
     IO(IOError),
     RecordParse(RecordParseError),
-    Process(TestError)
+    Process(TestError),
+    /// Error with the tracefile, the line, the source file and the test where it was raised
+    Context(MergeContext, Box<MergeError>)
+}
+
+impl MergeError {
+    /// Returns the error without the context
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { merge_files, MergeError };
+    ///
+    /// let err = merge_files(&[
+    ///     "tests/fixtures/merged/ne_checksum/fixture1.info",
+    ///     "tests/fixtures/merged/ne_checksum/fixture2.info"
+    /// ]).unwrap_err();
+    ///
+    /// assert!(matches!(err, MergeError::Context(_, _)));
+    /// assert!(matches!(err.root_cause(), MergeError::Process(_)));
+    /// ```
+    pub fn root_cause(&self) -> &MergeError {
+        match self {
+            MergeError::Context(_, err) => err.root_cause(),
+            err => err
+        }
+    }
 }
 
 impl_from_error!(IOError, MergeError::IO);
@@ -199,9 +347,19 @@ impl_from_error!(TestError, MergeError::Process);
 impl fmt::Display for MergeError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &MergeError::IO(ref err) => write!(f, "{}", err),
-            &MergeError::RecordParse(ref err) => write!(f, "{}", err),
-            &MergeError::Process(ref err) => write!(f, "{}", err)
+            MergeError::IO(err) => write!(f, "{}", err),
+            MergeError::RecordParse(err) => write!(f, "{}", err),
+            MergeError::Process(err) => write!(f, "{}", err),
+            MergeError::Context(context, err) => write!(f, "{}: {}", context, err)
+        }
+    }
+}
+
+impl Error for MergeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MergeError::Context(_, err) => Some(&**err),
+            _ => None
         }
     }
 }
@@ -210,7 +368,9 @@ impl fmt::Display for MergeError {
 
 #[cfg(test)]
 mod tests {
-    use merger::ops:: { MergeError, TestError, ChecksumError, MergeLine };
+    use parser:: { RecordParseError };
+    use std::error:: { Error };
+    use merger::ops:: { MergeError, MergeContext, TestError, ChecksumError, MergeLine };
 
     #[test]
     fn merge_error_of_checksum() {
@@ -221,4 +381,29 @@ mod tests {
         let merge_error = MergeError::from(test_error);
         assert_eq!(merge_error.to_string(), "Source code checksums do not match: line: 1, left: xyz, right: zzz")
     }
+
+    #[test]
+    fn merge_error_with_context() {
+        let checksum_error = ChecksumError::Mismatch(
+            MergeLine::new(1, Some("xyz".to_string())),
+            MergeLine::new(1, Some("zzz".to_string()))
+        );
+        let context = MergeContext {
+            path: Some("a.info".into()),
+            line: Some(10),
+            source_file: Some("/a.c".to_string()),
+            test_name: Some("test".to_string())
+        };
+        let merge_error = MergeError::Context(context, Box::new(MergeError::from(checksum_error)));
+        assert_eq!(merge_error.to_string(), "a.info:10, source file: /a.c, test: test: Source code checksums do not match: line: 1, left: xyz, right: zzz");
+
+        assert!(matches!(merge_error.root_cause(), MergeError::Process(_)));
+        assert!(matches!(merge_error.source().and_then(|err| err.downcast_ref()), Some(MergeError::Process(_))));
+
+        let context = MergeContext { path: Some("a.info".into()), source_file: Some("/a.c".to_string()), ..MergeContext::default() };
+        let parse_error = RecordParseError { line: 1, column: 1, message: "unexpected".to_string() };
+        let merge_error = MergeError::Context(context, Box::new(MergeError::RecordParse(parse_error)));
+        assert_eq!(merge_error.to_string(), "a.info, source file: /a.c: unexpected");
+        assert!(merge_error.root_cause().source().is_none());
+    }
 }
@@ -0,0 +1,136 @@
+// Copyright (c) 2015-2016 lcov-parser developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+#[cfg(feature = "regex")]
+extern crate regex;
+
+use std::path:: { Path, PathBuf, Component };
+
+/// Rule to rewrite the path of the source file while merging
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser:: { PathRule };
+///
+/// let rules = [
+///     PathRule::Normalize,
+///     PathRule::StripPrefix("/home/ci/agent-3/".to_string())
+/// ];
+/// let path = rules.iter().fold("/home/ci/agent-3/src/../src/./foo.c".to_string(), |path, rule| rule.apply(&path));
+///
+/// assert_eq!(path, "src/foo.c");
+/// ```
+#[derive(Debug, Clone)]
+pub enum PathRule {
+    /// Remove the prefix of the path, the path is kept if it does not start with the prefix
+    StripPrefix(String),
+    /// Replace the matches of the regular expression, the replacement can refer the groups as `$1`
+    #[cfg(feature = "regex")]
+    Replace(regex::Regex, String),
+    /// Make the path relative to the root, the path is kept if it is not under the root
+    RelativeTo(PathBuf),
+    /// Remove `.` and resolve `..` without accessing the file system
+    Normalize,
+    /// Convert the path to lowercase for the case insensitive file system
+    CaseFold
+}
+
+impl PathRule {
+    /// Returns the rule of `PathRule::Replace`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { PathRule };
+    ///
+    /// let rule = PathRule::replace("^/builds/[^/]+/", "").unwrap();
+    /// assert_eq!(rule.apply("/builds/x/src/foo.c"), "src/foo.c");
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn replace<S: Into<String>>(pattern: &str, replacement: S) -> Result<Self, regex::Error> {
+        Ok(PathRule::Replace(regex::Regex::new(pattern)?, replacement.into()))
+    }
+    pub fn apply(&self, path: &str) -> String {
+        match *self {
+            PathRule::StripPrefix(ref prefix) => path.strip_prefix(prefix.as_str()).unwrap_or(path).to_string(),
+            #[cfg(feature = "regex")]
+            PathRule::Replace(ref pattern, ref replacement) => pattern.replace_all(path, replacement.as_str()).into_owned(),
+            PathRule::RelativeTo(ref root) => match Path::new(path).strip_prefix(root) {
+                Ok(relative) => relative.to_string_lossy().into_owned(),
+                Err(_) => path.to_string()
+            },
+            PathRule::Normalize => normalize(path),
+            PathRule::CaseFold => path.to_lowercase()
+        }
+    }
+}
+
+fn normalize(path: &str) -> String {
+    let mut normalized = PathBuf::new();
+    let mut depth = 0;
+
+    for component in Path::new(path).components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir if depth > 0 => {
+                normalized.pop();
+                depth -= 1;
+            },
+            Component::ParentDir if normalized.has_root() => {},
+            Component::Normal(_) => {
+                normalized.push(component);
+                depth += 1;
+            },
+            _ => normalized.push(component)
+        }
+    }
+    normalized.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path:: { PathBuf };
+    use merger::path:: { PathRule };
+
+    #[test]
+    fn strip_prefix() {
+        let rule = PathRule::StripPrefix("/home/ci/".to_string());
+        assert_eq!(rule.apply("/home/ci/src/foo.c"), "src/foo.c");
+        assert_eq!(rule.apply("/builds/src/foo.c"), "/builds/src/foo.c");
+    }
+
+    #[test]
+    fn relative_to() {
+        let rule = PathRule::RelativeTo(PathBuf::from("/home/ci"));
+        assert_eq!(rule.apply("/home/ci/src/foo.c"), "src/foo.c");
+        assert_eq!(rule.apply("/home/circle/src/foo.c"), "/home/circle/src/foo.c");
+    }
+
+    #[test]
+    fn normalize() {
+        let rule = PathRule::Normalize;
+        assert_eq!(rule.apply("/src/./lib/../foo.c"), "/src/foo.c");
+        assert_eq!(rule.apply("/../foo.c"), "/foo.c");
+        assert_eq!(rule.apply("../src//foo.c"), "../src/foo.c");
+        assert_eq!(rule.apply("src/../../foo.c"), "../foo.c");
+    }
+
+    #[test]
+    fn case_fold() {
+        assert_eq!(PathRule::CaseFold.apply("C:/Src/Foo.c"), "c:/src/foo.c");
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn replace() {
+        let rule = PathRule::replace("^/home/ci/agent-[0-9]+/", "/src/").unwrap();
+        assert_eq!(rule.apply("/home/ci/agent-3/foo.c"), "/src/foo.c");
+        assert!(PathRule::replace("(", "").is_err());
+    }
+}
@@ -9,8 +9,9 @@
 //! Parser of LCOV report.
 
 use combine:: { parser, Parser, ParseError as CombinatorParseError, State };
-use record:: { LCOVRecord };
+use record:: { LCOVRecord, LCOVRecordRef, UnknownRecordRef };
 use combinator:: { record, report };
+use compression:: { TracefileReader };
 use std::fs:: { File };
 use std::result:: { Result };
 use std::io:: { Result as IOResult, Error as IOError, Read, BufRead, BufReader };
@@ -21,7 +22,7 @@ use std::error::Error;
 
 pub type ParseResult<T> = Result<T, RecordParseError>;
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct RecordParseError {
     pub line: u32,
     pub column: u32,
@@ -48,9 +49,6 @@ impl Display for RecordParseError {
 }
 
 impl Error for RecordParseError {
-    fn description(&self) -> &str {
-        &self.message[..]
-    }
 }
 
 #[derive(Debug)]
@@ -71,10 +69,10 @@ impl From<RecordParseError> for ParseError {
 }
 
 impl Error for ParseError {
-    fn description(&self) -> &str {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
-            &ParseError::IOError(ref err) => err.description(),
-            &ParseError::RecordParseError(ref err) => err.description(),
+            ParseError::IOError(err) => Some(err),
+            ParseError::RecordParseError(err) => Some(err),
         }
     }
 }
@@ -82,12 +80,23 @@ impl Error for ParseError {
 impl Display for ParseError {
     fn fmt(&self, formatter: &mut Formatter) -> FormatResult {
         match self {
-            &ParseError::IOError(ref err) => err.fmt(formatter),
-            &ParseError::RecordParseError(ref err) => err.fmt(formatter),
+            ParseError::IOError(err) => err.fmt(formatter),
+            ParseError::RecordParseError(err) => err.fmt(formatter),
         }
     }
 }
 
+/// Mode of the parser for unknown or malformed records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+pub enum ParseMode {
+    /// Fail with `ParseError::RecordParseError`
+    #[default]
+    Strict,
+    /// Return `LCOVRecord::Unknown`, skip blank lines and collect the errors as warnings
+    Lenient
+}
+
 /// Parse the record one line at a time
 ///
 /// # Examples
@@ -113,6 +122,9 @@ impl Display for ParseError {
 /// ```
 pub struct LCOVParser<T> {
     line: u32,
+    mode: ParseMode,
+    warnings: Vec<RecordParseError>,
+    buffer: String,
     reader: BufReader<T>
 }
 
@@ -120,9 +132,52 @@ impl<T: Read> LCOVParser<T> {
     pub fn new(reader: T) -> Self {
         LCOVParser {
             line: 0,
+            mode: ParseMode::Strict,
+            warnings: vec![],
+            buffer: String::new(),
             reader: BufReader::new(reader)
         }
     }
+    /// Set the mode for unknown or malformed records
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { LCOVParser, LCOVRecord, ParseMode, UnknownRecord };
+    ///
+    /// let mut parser = LCOVParser::new("VER:2.0\n\nTN:test_name\n".as_bytes());
+    /// parser.set_mode(ParseMode::Lenient);
+    ///
+    /// let records = parser.parse().unwrap();
+    /// let unknown = UnknownRecord { tag: "VER".to_string(), raw: "VER:2.0".to_string() };
+    ///
+    /// assert_eq!(records[0], LCOVRecord::Unknown(unknown));
+    /// assert_eq!(records[1], LCOVRecord::TestName(Some("test_name".to_string())));
+    /// assert_eq!(parser.warnings().len(), 2);
+    /// assert_eq!(parser.warnings()[1].line, 2);
+    /// ```
+    pub fn set_mode(&mut self, mode: ParseMode) {
+        self.mode = mode;
+    }
+    /// Returns the errors of the records skipped or returned as unknown by the lenient mode
+    pub fn warnings(&self) -> &[RecordParseError] {
+        &self.warnings
+    }
+    /// Returns the line number of the record read last, 0 before the first record
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { LCOVParser };
+    ///
+    /// let mut parser = LCOVParser::new("TN:test_name\nSF:/path/to/source.rs\n".as_bytes());
+    /// parser.next().unwrap();
+    ///
+    /// assert_eq!(parser.line(), 1);
+    /// ```
+    pub fn line(&self) -> u32 {
+        self.line
+    }
     pub fn parse(&mut self) -> Result<Vec<LCOVRecord>, ParseError> {
         let mut records = vec![];
         loop {
@@ -134,35 +189,98 @@ impl<T: Read> LCOVParser<T> {
         }
         Ok(records)
     }
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Result<Option<LCOVRecord>, ParseError> {
-        let mut line = String::new();
-        let size = self.reader.read_line(&mut line)?;
-        if size <= 0 {
-            return Ok(None);
-        }
-        self.line += 1;
-        let record = self.parse_record(line.as_str())?;
-        return Ok( Some(record) );
+        let record = self.next_ref()?;
+        Ok( record.map(LCOVRecord::from) )
     }
-    fn parse_record(&mut self, line: &str) -> Result<LCOVRecord, RecordParseError> {
-        match parse_record(line) {
-            Ok(record) => Ok(record),
+    /// Parse the next record without copying its names.
+    /// The record borrows the line buffer of the parser, which is reused by the next call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { LCOVParser, LCOVRecordRef };
+    ///
+    /// let mut parser = LCOVParser::new("TN:test_name\nSF:/path/to/source.rs\n".as_bytes());
+    /// let mut source_files = 0;
+    ///
+    /// while let Some(record) = parser.next_ref().unwrap() {
+    ///     if let LCOVRecordRef::SourceFile(_) = record {
+    ///         source_files += 1;
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(source_files, 1);
+    /// ```
+    pub fn next_ref(&mut self) -> Result<Option<LCOVRecordRef<'_>>, ParseError> {
+        loop {
+            self.buffer.clear();
+            let size = self.reader.read_line(&mut self.buffer)?;
+            if size == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+
+            let skip_blank_line = self.mode == ParseMode::Lenient && self.buffer.trim().is_empty();
+            if !skip_blank_line {
+                break;
+            }
+            if let Err(err) = parse_record_ref(self.buffer.as_str()) {
+                let warning = self.record_parse_error(err);
+                self.warnings.push(warning);
+            }
+        }
+
+        match parse_record_ref(self.buffer.as_str()) {
+            Ok(record) => Ok( Some(record) ),
             Err(err) => {
-                Err(RecordParseError {
-                    line: self.line.clone(),
-                    column: err.column,
-                    message: err.message
-                })
+                let err = self.record_parse_error(err);
+                if self.mode == ParseMode::Strict {
+                    return Err(ParseError::from(err));
+                }
+                self.warnings.push(err);
+                let unknown = UnknownRecordRef::from_line(self.buffer.as_str());
+                Ok( Some(LCOVRecordRef::Unknown(unknown)) )
             }
         }
     }
+    fn record_parse_error(&self, err: RecordParseError) -> RecordParseError {
+        RecordParseError {
+            line: self.line,
+            column: err.column,
+            message: err.message
+        }
+    }
+}
+
+/// Iterate over the records of the report
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser:: { LCOVParser, LCOVRecord };
+///
+/// let parser = LCOVParser::new("TN:test_name\nSF:/path/to/source.rs\n".as_bytes());
+/// let records: Result<Vec<LCOVRecord>, _> = parser.collect();
+///
+/// assert_eq!(records.unwrap()[1], LCOVRecord::SourceFile("/path/to/source.rs".to_string()));
+/// ```
+impl<T: Read> Iterator for LCOVParser<T> {
+    type Item = Result<LCOVRecord, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        LCOVParser::next(self).transpose()
+    }
 }
 
+/// Create a parser from the file as it is, the compressed tracefile is not decoded.
+/// Use `LCOVParser::open` to read the tracefiles compressed with gzip or zstd.
 pub trait FromFile<T> {
     fn from_file<P: AsRef<Path>>(path: P) -> IOResult<LCOVParser<T>>;
 }
 
-/// Create a parser from file
+/// Create a parser from file, the file is not decompressed, see `LCOVParser::open`
 ///
 /// # Examples
 ///
@@ -181,6 +299,26 @@ impl FromFile<File> for LCOVParser<File> {
     }
 }
 
+impl LCOVParser<TracefileReader> {
+    /// Create a parser from the tracefile, which is decoded on the fly if it is compressed.
+    /// An error of `ErrorKind::InvalidData` is returned when the feature of the compression is disabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { LCOVParser, LCOVRecord };
+    ///
+    /// let mut parser = LCOVParser::open("tests/fixtures/parser/report.lcov").unwrap();
+    /// let result = parser.next().unwrap();
+    ///
+    /// assert_eq!(result, Some(LCOVRecord::TestName(Some("test".to_string()))));
+    /// ```
+    pub fn open<P: AsRef<Path>>(path: P) -> IOResult<Self> {
+        let file = TracefileReader::open(path)?;
+        Ok(LCOVParser::new(file))
+    }
+}
+
 /// Parse the record
 ///
 /// # Examples
@@ -192,9 +330,25 @@ impl FromFile<File> for LCOVParser<File> {
 ///
 /// assert_eq!(result.unwrap(), LCOVRecord::TestName(Some("test_name".to_string())));
 /// ```
-
 #[inline]
 pub fn parse_record(input: &str) -> ParseResult<LCOVRecord> {
+    let record = parse_record_ref(input)?;
+    Ok(LCOVRecord::from(record))
+}
+
+/// Parse the record without copying its names
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser:: { LCOVRecordRef, parse_record_ref };
+///
+/// let result = parse_record_ref("TN:test_name\n");
+///
+/// assert_eq!(result.unwrap(), LCOVRecordRef::TestName(Some("test_name")));
+/// ```
+#[inline]
+pub fn parse_record_ref(input: &str) -> ParseResult<LCOVRecordRef<'_>> {
     let (record, _) = parser(record).parse(State::new(input))?;
     Ok(record)
 }
@@ -212,7 +366,6 @@ pub fn parse_record(input: &str) -> ParseResult<LCOVRecord> {
 /// assert_eq!(records.get(0).unwrap(), &LCOVRecord::TestName(Some("test_name".to_string())));
 /// assert_eq!(records.get(1).unwrap(), &LCOVRecord::SourceFile("/path/to/source.rs".to_string()));
 /// ```
-
 #[inline]
 pub fn parse_report(input: &str) -> ParseResult<Vec<LCOVRecord>> {
     let (records, _) = parser(report).parse(State::new(input))?;
@@ -0,0 +1,350 @@
+// Copyright (c) 2015-2016 lcov-parser developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Coverage of the lines added by the unified diff, such as the output of `git diff`.
+
+use std::fmt;
+use std::error:: { Error };
+use std::path:: { Path, Component };
+use std::collections::btree_map:: { BTreeMap };
+use report:: { Report };
+use report::summary:: { Summary };
+use report::coverage:: { Coverage };
+use report::attribute:: { SourceFile, LineNumber };
+use merger::ops:: { TestError };
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum PatchError {
+    /// Malformed hunk header, with the line number of the diff
+    InvalidHunkHeader(usize, String)
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::InvalidHunkHeader(line_number, line) => write!(f, "Invalid hunk header at line {}: {}", line_number, line)
+        }
+    }
+}
+
+impl Error for PatchError {
+}
+
+/// Lines added by the unified diff
+///
+/// The paths of the diff are mapped to the source files of the report as follows.
+///
+/// 1. The leading components of the path are removed, one by default for `a/` and `b/` of git.
+/// 2. If the source root is set, the source file is the path joined to the root.
+/// 3. Otherwise, the source file is the path, or the only source file which ends with the path.
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser:: { Patch, merge_files };
+///
+/// let report = merge_files(&[ "tests/fixtures/diff/head.info" ]).unwrap();
+/// let diff = concat!(
+///     "--- a/src/a.c\n",
+///     "+++ b/src/a.c\n",
+///     "@@ -1,0 +2,2 @@\n",
+///     "+  covered();\n",
+///     "+  uncovered();\n"
+/// );
+///
+/// let patch = Patch::parse(diff).unwrap();
+/// let coverage = patch.coverage(&report).unwrap();
+///
+/// assert_eq!(coverage.coverage().percentage(), Some(50.0));
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Patch {
+    files: BTreeMap<String, Vec<LineNumber>>,
+    strip: usize,
+    source_root: Option<String>
+}
+
+impl Patch {
+    pub fn parse(diff: &str) -> Result<Self, PatchError> {
+        let mut files: BTreeMap<String, Vec<LineNumber>> = BTreeMap::new();
+        let mut current: Option<String> = None;
+        let mut new_line: LineNumber = 0;
+        let mut old_remaining: u32 = 0;
+        let mut new_remaining: u32 = 0;
+
+        for (index, line) in diff.lines().enumerate() {
+            if old_remaining > 0 || new_remaining > 0 {
+                match line.chars().next() {
+                    Some('+') => {
+                        if let Some(ref path) = current {
+                            files.entry(path.clone()).or_default().push(new_line);
+                        }
+                        new_line = new_line.saturating_add(1);
+                        new_remaining = new_remaining.saturating_sub(1);
+                    },
+                    Some('-') => old_remaining = old_remaining.saturating_sub(1),
+                    Some('\\') => {},
+                    _ => {
+                        new_line = new_line.saturating_add(1);
+                        old_remaining = old_remaining.saturating_sub(1);
+                        new_remaining = new_remaining.saturating_sub(1);
+                    }
+                }
+                continue;
+            }
+            if let Some(path) = line.strip_prefix("+++ ") {
+                current = new_path(path);
+            } else if line.starts_with("@@ ") {
+                let (old_count, new_start, new_count) = hunk_header(line)
+                    .ok_or_else(|| PatchError::InvalidHunkHeader(index + 1, line.to_string()))?;
+                new_line = new_start;
+                old_remaining = old_count;
+                new_remaining = new_count;
+            }
+        }
+        Ok(Patch {
+            files,
+            strip: 1,
+            source_root: None
+        })
+    }
+    /// Added lines of the files, the paths are the paths of the diff
+    pub fn files(&self) -> &BTreeMap<String, Vec<LineNumber>> {
+        &self.files
+    }
+    /// Set the number of the leading components removed from the paths of the diff, like `patch -p`
+    pub fn set_strip(&mut self, strip: usize) {
+        self.strip = strip;
+    }
+    /// Set the directory that the paths of the diff are relative to
+    pub fn set_source_root<S: Into<String>>(&mut self, source_root: S) {
+        self.source_root = Some(source_root.into());
+    }
+    /// Returns the coverage of the added lines, the tests of the file are combined
+    pub fn coverage(&self, report: &Report) -> Result<PatchCoverage, TestError> {
+        let mut files = BTreeMap::new();
+        let mut unmatched_files = vec![];
+
+        for (path, added_lines) in self.files.iter() {
+            let source_name = match self.source_name(path, report) {
+                Some(source_name) => source_name,
+                None => {
+                    unmatched_files.push(path.clone());
+                    continue;
+                }
+            };
+            let test = report.files().get(&source_name)
+                .map(|file| file.combined())
+                .transpose()?
+                .unwrap_or_default();
+
+            let mut file_coverage = FilePatchCoverage::default();
+            for line_number in added_lines.iter() {
+                match test.lines().get(line_number) {
+                    Some(line) if line.is_hit() => file_coverage.covered.push(*line_number),
+                    Some(_) => file_coverage.uncovered.push(*line_number),
+                    None => file_coverage.not_instrumented.push(*line_number)
+                }
+            }
+            files.insert(source_name, file_coverage);
+        }
+        Ok(PatchCoverage { files, unmatched_files })
+    }
+    fn source_name(&self, path: &str, report: &Report) -> Option<SourceFile> {
+        let path = strip_components(path, self.strip);
+        if let Some(ref source_root) = self.source_root {
+            let source_name = Path::new(source_root).join(&path).to_string_lossy().into_owned();
+            return report.files().get(&source_name).map(|_| source_name);
+        }
+        if report.files().contains_key(&path) {
+            return Some(path);
+        }
+        let suffix = format!("/{}", path);
+        let mut source_names = report.files().iter()
+            .map(|(source_name, _)| source_name)
+            .filter(|source_name| source_name.ends_with(&suffix));
+
+        match (source_names.next(), source_names.next()) {
+            (Some(source_name), None) => Some(source_name.clone()),
+            _ => None
+        }
+    }
+}
+
+/// Coverage of the added lines for each source file
+#[derive(Debug, PartialEq, Clone)]
+pub struct PatchCoverage {
+    files: BTreeMap<SourceFile, FilePatchCoverage>,
+    unmatched_files: Vec<String>
+}
+
+impl PatchCoverage {
+    pub fn files(&self) -> &BTreeMap<SourceFile, FilePatchCoverage> {
+        &self.files
+    }
+    /// Paths of the diff which are not found in the report
+    pub fn unmatched_files(&self) -> &[String] {
+        &self.unmatched_files
+    }
+    /// Returns the coverage of the instrumented lines added to all files
+    pub fn coverage(&self) -> Coverage {
+        self.files.values().fold(Coverage::default(), |coverage, file| coverage + file.coverage())
+    }
+}
+
+impl fmt::Display for PatchCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (source_name, file) in self.files.iter() {
+            writeln!(f, "{}: {}", source_name, file.coverage())?;
+            if !file.uncovered.is_empty() {
+                let lines: Vec<String> = file.uncovered.iter().map(|line| line.to_string()).collect();
+                writeln!(f, "  uncovered lines: {}", lines.join(", "))?;
+            }
+        }
+        writeln!(f, "patch coverage: {}", self.coverage())
+    }
+}
+
+/// Added lines of the source file, classified by the execution count
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FilePatchCoverage {
+    covered: Vec<LineNumber>,
+    uncovered: Vec<LineNumber>,
+    not_instrumented: Vec<LineNumber>
+}
+
+impl FilePatchCoverage {
+    pub fn covered(&self) -> &[LineNumber] {
+        &self.covered
+    }
+    pub fn uncovered(&self) -> &[LineNumber] {
+        &self.uncovered
+    }
+    /// Lines which have no execution count in the report, such as comments
+    pub fn not_instrumented(&self) -> &[LineNumber] {
+        &self.not_instrumented
+    }
+    pub fn coverage(&self) -> Coverage {
+        Coverage::new(self.covered.len(), self.covered.len() + self.uncovered.len())
+    }
+}
+
+/// Path of the new file, None if the file is deleted
+fn new_path(path: &str) -> Option<String> {
+    let path = path.split('\t').next().unwrap_or(path).trim_end();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path.strip_prefix('"')
+        .and_then(|path| path.strip_suffix('"'))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// Parse `@@ -<start>[,<count>] +<start>[,<count>] @@`, and returns the old count, the new start and the new count
+fn hunk_header(line: &str) -> Option<(u32, LineNumber, u32)> {
+    let mut ranges = line.trim_start_matches("@@ ").split(' ');
+    let old_range = ranges.next()?.strip_prefix('-')?;
+    let new_range = ranges.next()?.strip_prefix('+')?;
+    let (_, old_count) = hunk_range(old_range)?;
+    let (new_start, new_count) = hunk_range(new_range)?;
+    new_start.checked_add(new_count)?;
+    Some((old_count, new_start, new_count))
+}
+
+fn hunk_range(range: &str) -> Option<(u32, u32)> {
+    let mut values = range.splitn(2, ',');
+    let start = values.next()?.parse().ok()?;
+    let count = match values.next() {
+        Some(count) => count.parse().ok()?,
+        None => 1
+    };
+    Some((start, count))
+}
+
+fn strip_components(path: &str, strip: usize) -> String {
+    let components: Vec<_> = Path::new(path).components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect();
+    let stripped: Vec<_> = components.iter().skip(strip)
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    stripped.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use merger:: { merge_files };
+    use report::coverage:: { Coverage };
+    use patch:: { Patch, PatchError, hunk_header };
+
+    fn patch_fixture() -> Patch {
+        Patch::parse(&fs::read_to_string("tests/fixtures/patch/change.diff").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parse_diff() {
+        let patch = patch_fixture();
+        let files: Vec<_> = patch.files().iter()
+            .map(|(path, lines)| (path.as_str(), lines.clone()))
+            .collect();
+
+        assert_eq!(files, vec![
+            ("b/README.md", vec![ 2 ]),
+            ("b/src/a.c", vec![ 2, 3, 4, 11, 12 ])
+        ]);
+    }
+
+    #[test]
+    fn parse_hunk_header() {
+        assert_eq!(hunk_header("@@ -1,2 +3,4 @@ fn main()"), Some((2, 3, 4)));
+        assert_eq!(hunk_header("@@ -1 +1 @@"), Some((1, 1, 1)));
+        assert_eq!(hunk_header("@@ -0,0 +1 @@"), Some((0, 1, 1)));
+        assert_eq!(
+            Patch::parse("+++ b/a.c\n@@ -a +1 @@\n"),
+            Err(PatchError::InvalidHunkHeader(2, "@@ -a +1 @@".to_string()))
+        );
+        assert_eq!(hunk_header("@@ -1 +4294967294,1 @@"), Some((1, 4294967294, 1)));
+        assert_eq!(
+            Patch::parse("+++ b/a.c\n@@ -1 +4294967295,2 @@\n+a\n+b\n"),
+            Err(PatchError::InvalidHunkHeader(2, "@@ -1 +4294967295,2 @@".to_string()))
+        );
+        assert!(Patch::parse("+++ b/a.c\n@@ -1,3 +4294967294,1 @@\n+a\n b\n c\n").is_ok());
+    }
+
+    #[test]
+    fn patch_coverage() {
+        let report = merge_files(&[ "tests/fixtures/diff/head.info" ]).unwrap();
+        let coverage = patch_fixture().coverage(&report).unwrap();
+        let file = coverage.files().get("/src/a.c").unwrap();
+
+        assert_eq!(file.covered(), &[ 3, 11, 12 ]);
+        assert_eq!(file.uncovered(), &[ 2 ]);
+        assert_eq!(file.not_instrumented(), &[ 4 ]);
+        assert_eq!(coverage.unmatched_files(), &[ "b/README.md".to_string() ]);
+        assert_eq!(coverage.coverage(), Coverage::new(3, 4));
+        assert_eq!(coverage.to_string(), concat!(
+            "/src/a.c: 75.0% (3 of 4)\n",
+            "  uncovered lines: 2\n",
+            "patch coverage: 75.0% (3 of 4)\n"
+        ));
+    }
+
+    #[test]
+    fn patch_coverage_with_source_root() {
+        let report = merge_files(&[ "tests/fixtures/diff/head.info" ]).unwrap();
+        let mut patch = patch_fixture();
+        patch.set_strip(2);
+        patch.set_source_root("/src");
+
+        let coverage = patch.coverage(&report).unwrap();
+        assert_eq!(coverage.files().keys().collect::<Vec<_>>(), vec![ "/src/a.c" ]);
+        assert_eq!(coverage.unmatched_files(), &[ "b/README.md".to_string() ]);
+    }
+}
@@ -12,51 +12,277 @@ use std::convert:: { From };
 use std::option:: { Option };
 use std::io:: { Result };
 use std::io::prelude::*;
+use std::fmt;
 use parser:: { parse_record };
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(tag = "type", content = "value", rename_all = "snake_case"))]
 pub enum LCOVRecord
 {
     TestName(Option<String>),         // TN:<test name>
     SourceFile(String),               // SF:<absolute path to the source file>
     Data(LineData),                   // DA:<line number>,<execution count>[,<checksum>]
-    FunctionName(FunctionName),       // FN:<line number of function start>,<function name> for each function
+    FunctionName(FunctionName),       // FN:<line number of function start>[,<line number of function end>],<function name> for each function
     FunctionData(FunctionData),       // FNDA:<execution count>,<function name>
+    FunctionLine(FunctionLine),       // FNL:<index>,<line number of function start>[,<line number of function end>]
+    FunctionAlias(FunctionAlias),     // FNA:<index>,<execution count>,<function name>
     FunctionsFound(u32),              // FNF:<number of functions found>
     FunctionsHit(u32),                // FNH:<number of function hit>
     LinesHit(u32),                    // LH:<number of lines with an execution count> greater than 0
     LinesFound(u32),                  // LF:<number of instrumented lines>
-    BranchData(BranchData),           // BRDA:<line number>,<block number>,<branch number>,<taken>
+    BranchData(BranchData),           // BRDA:<line number>,<block number>,<branch number>,<taken or - if never executed>
     BranchesFound(u32),               // BRF:<number of branches found>
     BranchesHit(u32),                 // BRH:<number of branches hit>
-    EndOfRecord                       // end_of_record
+    EndOfRecord,                      // end_of_record
+    Unknown(UnknownRecord)            // unknown or malformed record, only returned by the lenient parser
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LineData {
     pub line: u32,
-    pub count: u32,
+    pub count: u64,
     pub checksum: Option<String> // MD5
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FunctionName {
     pub name: String,
-    pub line: u32
+    pub line: u32,
+    pub end: Option<u32>
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FunctionData {
     pub name: String,
-    pub count: u32
+    pub count: u64
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FunctionLine {
+    pub index: u32,
+    pub start: u32,
+    pub end: Option<u32>
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FunctionAlias {
+    pub index: u32,
+    pub count: u64,
+    pub name: String
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UnknownRecord {
+    pub tag: String,
+    pub raw: String
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BranchData {
     pub line: u32,
     pub block: u32,
     pub branch: u32,
-    pub taken: u32
+    pub taken: Option<u64> // None if the block was never executed
+}
+
+/// Record borrowing the names from the parsed line
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser:: { LCOVRecord, LCOVRecordRef, parse_record_ref };
+///
+/// let line = String::from("SF:/path/to/source.rs\n");
+/// let record = parse_record_ref(&line).unwrap();
+///
+/// assert_eq!(record, LCOVRecordRef::SourceFile("/path/to/source.rs"));
+/// assert_eq!(LCOVRecord::from(record), LCOVRecord::SourceFile("/path/to/source.rs".to_string()));
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LCOVRecordRef<'a>
+{
+    TestName(Option<&'a str>),
+    SourceFile(&'a str),
+    Data(LineDataRef<'a>),
+    FunctionName(FunctionNameRef<'a>),
+    FunctionData(FunctionDataRef<'a>),
+    FunctionLine(FunctionLine),
+    FunctionAlias(FunctionAliasRef<'a>),
+    FunctionsFound(u32),
+    FunctionsHit(u32),
+    LinesHit(u32),
+    LinesFound(u32),
+    BranchData(BranchData),
+    BranchesFound(u32),
+    BranchesHit(u32),
+    EndOfRecord,
+    Unknown(UnknownRecordRef<'a>)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LineDataRef<'a> {
+    pub line: u32,
+    pub count: u64,
+    pub checksum: Option<&'a str>
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FunctionNameRef<'a> {
+    pub name: &'a str,
+    pub line: u32,
+    pub end: Option<u32>
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FunctionDataRef<'a> {
+    pub name: &'a str,
+    pub count: u64
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FunctionAliasRef<'a> {
+    pub index: u32,
+    pub count: u64,
+    pub name: &'a str
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct UnknownRecordRef<'a> {
+    pub tag: &'a str,
+    pub raw: &'a str
+}
+
+impl<'a> UnknownRecordRef<'a> {
+    /// Create the record from the line, the tag is the part before the first colon
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { UnknownRecordRef };
+    ///
+    /// let record = UnknownRecordRef::from_line("VER:2.0\n");
+    ///
+    /// assert_eq!(record, UnknownRecordRef { tag: "VER", raw: "VER:2.0" });
+    /// ```
+    pub fn from_line(line: &'a str) -> Self {
+        let raw = line.trim_end_matches(['\r', '\n']);
+        let tag = raw.split(':').next().unwrap_or(raw);
+        UnknownRecordRef { tag, raw }
+    }
+}
+
+impl<'a> From<LineDataRef<'a>> for LCOVRecordRef<'a> {
+    fn from(input: LineDataRef<'a>) -> Self {
+        LCOVRecordRef::Data(input)
+    }
+}
+
+impl<'a> From<FunctionNameRef<'a>> for LCOVRecordRef<'a> {
+    fn from(input: FunctionNameRef<'a>) -> Self {
+        LCOVRecordRef::FunctionName(input)
+    }
+}
+
+impl<'a> From<FunctionDataRef<'a>> for LCOVRecordRef<'a> {
+    fn from(input: FunctionDataRef<'a>) -> Self {
+        LCOVRecordRef::FunctionData(input)
+    }
+}
+
+impl<'a> From<FunctionLine> for LCOVRecordRef<'a> {
+    fn from(input: FunctionLine) -> Self {
+        LCOVRecordRef::FunctionLine(input)
+    }
+}
+
+impl<'a> From<FunctionAliasRef<'a>> for LCOVRecordRef<'a> {
+    fn from(input: FunctionAliasRef<'a>) -> Self {
+        LCOVRecordRef::FunctionAlias(input)
+    }
+}
+
+impl<'a> From<BranchData> for LCOVRecordRef<'a> {
+    fn from(input: BranchData) -> Self {
+        LCOVRecordRef::BranchData(input)
+    }
+}
+
+impl<'a> From<LineDataRef<'a>> for LineData {
+    fn from(input: LineDataRef<'a>) -> Self {
+        LineData {
+            line: input.line,
+            count: input.count,
+            checksum: input.checksum.map(String::from)
+        }
+    }
+}
+
+impl<'a> From<FunctionNameRef<'a>> for FunctionName {
+    fn from(input: FunctionNameRef<'a>) -> Self {
+        FunctionName {
+            name: input.name.to_string(),
+            line: input.line,
+            end: input.end
+        }
+    }
+}
+
+impl<'a> From<FunctionDataRef<'a>> for FunctionData {
+    fn from(input: FunctionDataRef<'a>) -> Self {
+        FunctionData {
+            name: input.name.to_string(),
+            count: input.count
+        }
+    }
+}
+
+impl<'a> From<FunctionAliasRef<'a>> for FunctionAlias {
+    fn from(input: FunctionAliasRef<'a>) -> Self {
+        FunctionAlias {
+            index: input.index,
+            count: input.count,
+            name: input.name.to_string()
+        }
+    }
+}
+
+impl<'a> From<UnknownRecordRef<'a>> for UnknownRecord {
+    fn from(input: UnknownRecordRef<'a>) -> Self {
+        UnknownRecord {
+            tag: input.tag.to_string(),
+            raw: input.raw.to_string()
+        }
+    }
+}
+
+impl<'a> From<LCOVRecordRef<'a>> for LCOVRecord {
+    fn from(input: LCOVRecordRef<'a>) -> Self {
+        match input {
+            LCOVRecordRef::TestName(name) => LCOVRecord::TestName(name.map(String::from)),
+            LCOVRecordRef::SourceFile(name) => LCOVRecord::SourceFile(name.to_string()),
+            LCOVRecordRef::Data(data) => LCOVRecord::from(LineData::from(data)),
+            LCOVRecordRef::FunctionName(func_name) => LCOVRecord::from(FunctionName::from(func_name)),
+            LCOVRecordRef::FunctionData(func_data) => LCOVRecord::from(FunctionData::from(func_data)),
+            LCOVRecordRef::FunctionLine(func_line) => LCOVRecord::from(func_line),
+            LCOVRecordRef::FunctionAlias(func_alias) => LCOVRecord::from(FunctionAlias::from(func_alias)),
+            LCOVRecordRef::FunctionsFound(found) => LCOVRecord::FunctionsFound(found),
+            LCOVRecordRef::FunctionsHit(hit) => LCOVRecord::FunctionsHit(hit),
+            LCOVRecordRef::LinesHit(hit) => LCOVRecord::LinesHit(hit),
+            LCOVRecordRef::LinesFound(found) => LCOVRecord::LinesFound(found),
+            LCOVRecordRef::BranchData(branch_data) => LCOVRecord::from(branch_data),
+            LCOVRecordRef::BranchesFound(found) => LCOVRecord::BranchesFound(found),
+            LCOVRecordRef::BranchesHit(hit) => LCOVRecord::BranchesHit(hit),
+            LCOVRecordRef::EndOfRecord => LCOVRecord::EndOfRecord,
+            LCOVRecordRef::Unknown(unknown) => LCOVRecord::Unknown(UnknownRecord::from(unknown))
+        }
+    }
 }
 
 /// Parse the record from &str.
@@ -95,12 +321,155 @@ impl From<FunctionData> for LCOVRecord {
     }
 }
 
+impl From<FunctionLine> for LCOVRecord {
+    fn from(input: FunctionLine) -> Self {
+        LCOVRecord::FunctionLine(input)
+    }
+}
+
+impl From<FunctionAlias> for LCOVRecord {
+    fn from(input: FunctionAlias) -> Self {
+        LCOVRecord::FunctionAlias(input)
+    }
+}
+
 impl From<BranchData> for LCOVRecord {
     fn from(input: BranchData) -> Self {
         LCOVRecord::BranchData(input)
     }
 }
 
+impl fmt::Display for LCOVRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LCOVRecord::TestName(Some(name)) => write!(f, "TN:{}", name),
+            LCOVRecord::TestName(None) => write!(f, "TN:"),
+            LCOVRecord::SourceFile(name) => write!(f, "SF:{}", name),
+            LCOVRecord::Data(data) => write!(f, "{}", data),
+            LCOVRecord::FunctionName(func_name) => write!(f, "{}", func_name),
+            LCOVRecord::FunctionData(func_data) => write!(f, "{}", func_data),
+            LCOVRecord::FunctionLine(func_line) => write!(f, "{}", func_line),
+            LCOVRecord::FunctionAlias(func_alias) => write!(f, "{}", func_alias),
+            LCOVRecord::FunctionsFound(found) => write!(f, "FNF:{}", found),
+            LCOVRecord::FunctionsHit(hit) => write!(f, "FNH:{}", hit),
+            LCOVRecord::LinesHit(hit) => write!(f, "LH:{}", hit),
+            LCOVRecord::LinesFound(found) => write!(f, "LF:{}", found),
+            LCOVRecord::BranchData(branch_data) => write!(f, "{}", branch_data),
+            LCOVRecord::BranchesFound(found) => write!(f, "BRF:{}", found),
+            LCOVRecord::BranchesHit(hit) => write!(f, "BRH:{}", hit),
+            LCOVRecord::EndOfRecord => write!(f, "end_of_record"),
+            LCOVRecord::Unknown(unknown) => write!(f, "{}", unknown.raw)
+        }
+    }
+}
+
+impl fmt::Display for LineData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.checksum {
+            Some(ref checksum) => write!(f, "DA:{},{},{}", self.line, self.count, checksum),
+            None => write!(f, "DA:{},{}", self.line, self.count)
+        }
+    }
+}
+
+impl fmt::Display for FunctionName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "FN:{},{},{}", self.line, end, self.name),
+            None => write!(f, "FN:{},{}", self.line, self.name)
+        }
+    }
+}
+
+impl fmt::Display for FunctionData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FNDA:{},{}", self.count, self.name)
+    }
+}
+
+impl fmt::Display for FunctionLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "FNL:{},{},{}", self.index, self.start, end),
+            None => write!(f, "FNL:{},{}", self.index, self.start)
+        }
+    }
+}
+
+impl fmt::Display for FunctionAlias {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FNA:{},{},{}", self.index, self.count, self.name)
+    }
+}
+
+impl fmt::Display for BranchData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.taken {
+            Some(taken) => write!(f, "BRDA:{},{},{},{}", self.line, self.block, self.branch, taken),
+            None => write!(f, "BRDA:{},{},{},-", self.line, self.block, self.branch)
+        }
+    }
+}
+
 pub trait RecordWrite {
     fn write_records<T: Write>(&self, output: &mut T) -> Result<()>;
 }
+
+impl RecordWrite for LCOVRecord {
+    fn write_records<T: Write>(&self, output: &mut T) -> Result<()> {
+        writeln!(output, "{}", self)
+    }
+}
+
+impl RecordWrite for [LCOVRecord] {
+    fn write_records<T: Write>(&self, output: &mut T) -> Result<()> {
+        for record in self.iter() {
+            record.write_records(output)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the records one line at a time
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser:: { LCOVRecord, RecordWriter, parse_report };
+///
+/// let records = parse_report("TN:test_name\nSF:/path/to/source.rs\nend_of_record\n").unwrap();
+/// let mut writer = RecordWriter::new(vec![]);
+///
+/// for record in records.iter() {
+///     writer.write(record).unwrap();
+/// }
+/// let output = writer.into_inner();
+///
+/// assert_eq!(String::from_utf8(output).unwrap(), "TN:test_name\nSF:/path/to/source.rs\nend_of_record\n");
+/// ```
+pub struct RecordWriter<W: Write> {
+    output: W
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub fn new(output: W) -> Self {
+        RecordWriter {
+            output
+        }
+    }
+    pub fn write(&mut self, record: &LCOVRecord) -> Result<()> {
+        record.write_records(&mut self.output)
+    }
+    pub fn write_all<'a, I: IntoIterator<Item=&'a LCOVRecord>>(&mut self, records: I) -> Result<()> {
+        for record in records {
+            self.write(record)?;
+        }
+        Ok(())
+    }
+    pub fn flush(&mut self) -> Result<()> {
+        self.output.flush()
+    }
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+}
@@ -11,7 +11,7 @@ use counter::Hit;
 pub type TestName = String;
 pub type SourceFile = String;
 pub type LineNumber = u32;
-pub type ExecutionCount = u32;
+pub type ExecutionCount = u64;
 pub type FunctionName = String;
 pub type CheckSum = String;
 
@@ -9,8 +9,11 @@
 use std::io;
 use std::cmp::PartialEq;
 use std::fmt:: { Display, Formatter, Result };
+use std::str:: { FromStr };
+use std::error:: { Error };
 use std::collections::btree_map:: { BTreeMap };
 use std::convert::{ From };
+use std::ops:: { RangeBounds };
 use record:: { BranchData };
 use merger::ops:: { TryMerge, MergeResult, MergeBranch, BranchError };
 use record:: { RecordWrite };
@@ -56,12 +59,65 @@ impl Display for BranchUnit {
     }
 }
 
+/// Parse the string form of the branch unit, `block-branch`
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser::branch::BranchUnit;
+///
+/// assert_eq!("1-2".parse(), Ok(BranchUnit::new(1, 2)));
+/// assert!("1".parse::<BranchUnit>().is_err());
+/// ```
+impl FromStr for BranchUnit {
+    type Err = ParseBranchUnitError;
+
+    fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+        let mut units = s.splitn(2, '-');
+        let block = units.next().and_then(|block| block.parse().ok());
+        let branch = units.next().and_then(|branch| branch.parse().ok());
+        match (block, branch) {
+            (Some(block), Some(branch)) => Ok(BranchUnit::new(block, branch)),
+            _ => Err(ParseBranchUnitError(s.to_string()))
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseBranchUnitError(String);
+
+impl Display for ParseBranchUnitError {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "Invalid branch unit, expected `block-branch`: {}", self.0)
+    }
+}
+
+impl Error for ParseBranchUnitError {
+}
+
+/// The branch unit is serialized as the string form, because it is the key of the map
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for BranchUnit {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for BranchUnit {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<Self, D::Error> {
+        let unit = String::deserialize(deserializer)?;
+        unit.parse().map_err(::serde::de::Error::custom)
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Branch {
     line_number: LineNumber,
     block: u32,
     branch: u32,
-    execution_count: ExecutionCount
+    execution_count: Option<ExecutionCount>
 }
 
 impl Branch {
@@ -69,13 +125,13 @@ impl Branch {
         line_number: LineNumber,
         block: u32,
         branch: u32,
-        execution_count: ExecutionCount
+        execution_count: Option<ExecutionCount>
     ) -> Self {
         Branch {
-            line_number: line_number,
-            block: block,
-            branch: branch,
-            execution_count: execution_count
+            line_number,
+            block,
+            branch,
+            execution_count
         }
     }
     pub fn line_number(&self) -> &LineNumber {
@@ -87,8 +143,19 @@ impl Branch {
     pub fn branch(&self) -> &u32 {
         &self.branch
     }
-    pub fn execution_count(&self) -> &ExecutionCount {
-        &self.execution_count
+    /// Returns None if the block of the branch was never executed
+    pub fn execution_count(&self) -> Option<&ExecutionCount> {
+        self.execution_count.as_ref()
+    }
+    pub fn is_executed(&self) -> bool {
+        self.execution_count.is_some()
+    }
+    fn merge_execution_count(&mut self, execution_count: Option<&ExecutionCount>) {
+        self.execution_count = match (self.execution_count, execution_count) {
+            (Some(current), Some(other)) => Some(current.saturating_add(*other)),
+            (None, Some(other)) => Some(*other),
+            (current, None) => current
+        };
     }
 }
 
@@ -102,7 +169,7 @@ impl PartialEq<BranchData> for Branch {
         if !branch_matched {
             return false;
         }
-        return true;
+        true
     }
 }
 
@@ -116,7 +183,7 @@ impl PartialEq<Branch> for Branch {
         if !branch_matched {
             return false;
         }
-        return true;
+        true
     }
 }
 
@@ -149,7 +216,7 @@ impl<'a> TryMerge<&'a BranchData> for Branch {
                 )
             );
         }
-        self.execution_count += data.taken;
+        self.merge_execution_count(data.taken.as_ref());
         Ok(())
     }
 }
@@ -166,14 +233,17 @@ impl<'a> TryMerge<&'a Branch> for Branch {
                 )
             );
         }
-        self.execution_count += *other.execution_count();
+        self.merge_execution_count(other.execution_count());
         Ok(())
     }
 }
 
 impl Hit for Branch {
     fn is_hit(&self) -> bool {
-        self.execution_count.is_hit()
+        match self.execution_count {
+            Some(ref execution_count) => execution_count.is_hit(),
+            None => false
+        }
     }
 }
 
@@ -181,10 +251,17 @@ impl Hit for Branch {
 
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct BranchBlocks {
     blocks: BTreeMap<BranchUnit, Branch>
 }
 
+impl Default for BranchBlocks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BranchBlocks {
     pub fn new() -> Self {
         BranchBlocks {
@@ -219,12 +296,11 @@ impl<'a> TryMerge<&'a BranchData> for BranchBlocks {
 
     fn try_merge(&mut self, data: &'a BranchData) -> MergeResult<Self::Err> {
         let unit = BranchUnit::new(data.block, data.branch);
-        if !self.blocks.contains_key(&unit) {
-            self.blocks.insert(unit, Branch::from(data));
-            return Ok(());
+        if let Some(block) = self.blocks.get_mut(&unit) {
+            return block.try_merge(data);
         }
-        let block = self.blocks.get_mut(&unit).unwrap();
-        block.try_merge(data)
+        self.blocks.insert(unit, Branch::from(data));
+        Ok(())
     }
 }
 
@@ -233,16 +309,34 @@ impl_try_merge_self_summary!(BranchBlocks:blocks, BranchError);
 
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct Branches {
     branches: BTreeMap<LineNumber, BranchBlocks>
 }
 
+impl Default for Branches {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Branches {
     pub fn new() -> Self {
         Branches {
             branches: BTreeMap::new()
         }
     }
+    /// Returns the branches within the range of line numbers
+    pub fn range<R: RangeBounds<LineNumber>>(&self, range: R) -> Branches {
+        let branches = self.branches.range(range)
+            .map(|(line_number, blocks)| (*line_number, blocks.clone()))
+            .collect();
+        Branches { branches }
+    }
+    /// Keep the branches on the lines which the predicate returns true for
+    pub fn retain<F: FnMut(&LineNumber) -> bool>(&mut self, mut predicate: F) {
+        self.branches.retain(|line_number, _| predicate(line_number));
+    }
 }
 
 impl HitCounter for Branches {
@@ -280,8 +374,12 @@ impl Display for Branches {
         }
         for (line_number, blocks) in self.iter() {
             for (_, branch) in blocks.iter() {
-                writeln!(f, "BRDA:{},{},{},{}",
-                    line_number, branch.block(), branch.branch(), branch.execution_count())?;
+                match branch.execution_count() {
+                    Some(execution_count) => writeln!(f, "BRDA:{},{},{},{}",
+                        line_number, branch.block(), branch.branch(), execution_count)?,
+                    None => writeln!(f, "BRDA:{},{},{},-",
+                        line_number, branch.block(), branch.branch())?
+                }
             }
         }
         writeln!(f, "BRF:{}", self.found_count())?;
@@ -297,21 +395,16 @@ impl<'a> TryMerge<&'a BranchData> for Branches {
     type Err = BranchError;
 
     fn try_merge(&mut self, data: &'a BranchData) -> MergeResult<Self::Err> {
-        if self.branches.contains_key(&data.line) {
-            let blocks = self.branches.get_mut(&data.line).unwrap();
-            blocks.try_merge(data)
-        } else {
-            let blocks = {
-                let mut blocks = BranchBlocks::new();
-                let _ = blocks.try_merge(data)?;
-                blocks
-            };
-            self.branches.insert(
-                data.line.clone(),
-                blocks
-            );
-            Ok(())
+        if let Some(blocks) = self.branches.get_mut(&data.line) {
+            return blocks.try_merge(data);
         }
+        let blocks = {
+            let mut blocks = BranchBlocks::new();
+            blocks.try_merge(data)?;
+            blocks
+        };
+        self.branches.insert(data.line, blocks);
+        Ok(())
     }
 }
 
@@ -349,21 +442,21 @@ mod tests {
     #[test]
     fn add_branch_data() {
         let mut branches = BranchBlocks::new();
-        let b1 = &BranchData { line: 1, block: 0, branch: 1, taken: 1 };
-        let b2 = &BranchData { line: 1, block: 0, branch: 1, taken: 1 };
+        let b1 = &BranchData { line: 1, block: 0, branch: 1, taken: Some(1) };
+        let b2 = &BranchData { line: 1, block: 0, branch: 1, taken: Some(1) };
 
         branches.try_merge(b1).unwrap();
         branches.try_merge(b2).unwrap();
 
-        let branch = Branch::new(1, 0, 1, 2);
+        let branch = Branch::new(1, 0, 1, Some(2));
         assert_eq!(branches.get(&BranchUnit::new(0, 1)), Some(&branch));
     }
 
     #[test]
     fn append_branches() {
         let mut branches = BranchBlocks::new();
-        let b1 = &BranchData { line: 1, block: 0, branch: 1, taken: 1 };
-        let b2 = &BranchData { line: 1, block: 0, branch: 1, taken: 1 };
+        let b1 = &BranchData { line: 1, block: 0, branch: 1, taken: Some(1) };
+        let b2 = &BranchData { line: 1, block: 0, branch: 1, taken: Some(1) };
 
         branches.try_merge(b1).unwrap();
         branches.try_merge(b2).unwrap();
@@ -371,15 +464,15 @@ mod tests {
         let cloned_branches = branches.clone();
         branches.try_merge(&cloned_branches).unwrap();
 
-        let branch = Branch::new(1, 0, 1, 2);
+        let branch = Branch::new(1, 0, 1, Some(2));
         assert_eq!(branches.get(&BranchUnit::new(0, 1)), Some(&branch));
     }
 
     #[test]
     fn branch_blocks_hit_count_and_found_count() {
         let mut branches = BranchBlocks::new();
-        let b1 = &BranchData { line: 1, block: 0, branch: 1, taken: 1 };
-        let b2 = &BranchData { line: 1, block: 0, branch: 2, taken: 0 };
+        let b1 = &BranchData { line: 1, block: 0, branch: 1, taken: Some(1) };
+        let b2 = &BranchData { line: 1, block: 0, branch: 2, taken: Some(0) };
 
         branches.try_merge(b1).unwrap();
         branches.try_merge(b2).unwrap();
@@ -391,10 +484,44 @@ mod tests {
     #[test]
     fn branches_hit_count_and_found_count() {
         let mut branches = Branches::new();
-        branches.try_merge(&BranchData { line: 1, block: 0, branch: 1, taken: 1 }).unwrap();
-        branches.try_merge(&BranchData { line: 1, block: 0, branch: 2, taken: 0 }).unwrap();
+        branches.try_merge(&BranchData { line: 1, block: 0, branch: 1, taken: Some(1) }).unwrap();
+        branches.try_merge(&BranchData { line: 1, block: 0, branch: 2, taken: Some(0) }).unwrap();
 
         assert_eq!(branches.hit_count(), 1);
         assert_eq!(branches.found_count(), 2);
     }
+
+    #[test]
+    fn merge_not_executed_branch() {
+        let mut branches = BranchBlocks::new();
+        branches.try_merge(&BranchData { line: 1, block: 0, branch: 1, taken: None }).unwrap();
+        branches.try_merge(&BranchData { line: 1, block: 0, branch: 2, taken: None }).unwrap();
+        branches.try_merge(&BranchData { line: 1, block: 0, branch: 2, taken: Some(0) }).unwrap();
+
+        let not_executed = branches.get(&BranchUnit::new(0, 1)).unwrap();
+        assert_eq!(not_executed.execution_count(), None);
+        assert!(!not_executed.is_executed());
+
+        let executed = branches.get(&BranchUnit::new(0, 2)).unwrap();
+        assert_eq!(executed.execution_count(), Some(&0));
+        assert!(executed.is_executed());
+
+        assert_eq!(branches.hit_count(), 0);
+        assert_eq!(branches.found_count(), 2);
+    }
+
+    #[test]
+    fn display_not_executed_branch() {
+        let mut branches = Branches::new();
+        branches.try_merge(&BranchData { line: 1, block: 0, branch: 1, taken: Some(2) }).unwrap();
+        branches.try_merge(&BranchData { line: 1, block: 0, branch: 2, taken: None }).unwrap();
+
+        let expected = concat!(
+            "BRDA:1,0,1,2\n",
+            "BRDA:1,0,2,-\n",
+            "BRF:2\n",
+            "BRH:1\n"
+        );
+        assert_eq!(branches.to_string(), expected);
+    }
 }
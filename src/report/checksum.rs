@@ -0,0 +1,238 @@
+// Copyright (c) 2015-2016 lcov-parser developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Checksums of the lines computed from the source files, like `geninfo --checksum`.
+//! The checksum is the MD5 of the line without the line ending, encoded in base64 without the padding.
+
+extern crate md5;
+
+use std::fs;
+use std::fmt;
+use std::io:: { Result, ErrorKind };
+use std::path:: { PathBuf };
+use std::collections:: { BTreeSet };
+use report:: { Report, source_path };
+use report::test:: { Test };
+use report::summary:: { Summary };
+use report::attribute:: { SourceFile, LineNumber, CheckSum };
+
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Returns the checksum of the line
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser::checksum:: { line_checksum };
+///
+/// assert_eq!(line_checksum("  func1(0, 2);"), "PF4Rz2r7RTliO9u6bZ7h6g");
+/// ```
+pub fn line_checksum(text: &str) -> CheckSum {
+    base64(&md5::compute(text.as_bytes()).0)
+}
+
+/// Line whose checksum differs from the source file
+#[derive(Debug, PartialEq, Clone)]
+pub struct StaleLine {
+    pub source_file: SourceFile,
+    pub line_number: LineNumber,
+    /// Checksum in the report
+    pub expected: CheckSum,
+    /// Checksum of the source file, None if the source file or the line does not exist
+    pub actual: Option<CheckSum>
+}
+
+impl fmt::Display for StaleLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.actual {
+            Some(ref actual) => write!(f, "Checksum mismatch: {}:{}, expected: {}, actual: {}", self.source_file, self.line_number, self.expected, actual),
+            None => write!(f, "Line not found: {}:{}, expected: {}", self.source_file, self.line_number, self.expected)
+        }
+    }
+}
+
+/// Fill or verify the checksums of the lines with the source files
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser:: { merge_files };
+/// use lcov_parser::checksum:: { SourceChecksum };
+///
+/// let report = merge_files(&[ "tests/fixtures/merged/eq_checksum/fixture1.info" ]).unwrap();
+///
+/// let mut checksum = SourceChecksum::new();
+/// checksum.set_source_root("tests/fixtures/merged/eq_checksum");
+///
+/// assert!(checksum.verify(&report).unwrap().is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SourceChecksum {
+    source_root: Option<PathBuf>
+}
+
+impl SourceChecksum {
+    pub fn new() -> Self {
+        SourceChecksum {
+            source_root: None
+        }
+    }
+    /// Set the directory of the source files whose lines are hashed, the path of the report is used if the root is not set
+    pub fn set_source_root<P: Into<PathBuf>>(&mut self, source_root: P) {
+        self.source_root = Some(source_root.into());
+    }
+    /// Returns the report which has the checksums of the source files.
+    /// The existing checksums are replaced, and the source file which is not found is kept as it is.
+    pub fn fill(&self, report: &Report) -> Result<Report> {
+        report.map_tests(|source_name| {
+            Ok(self.read_checksums(source_name)?.map(|checksums| move |test: &mut Test| {
+                test.set_checksums(|line_number| source_line(&checksums, line_number).cloned());
+            }))
+        })
+    }
+    /// Returns the lines whose checksums differ from the source files.
+    /// The lines without the checksum are not verified.
+    pub fn verify(&self, report: &Report) -> Result<Vec<StaleLine>> {
+        let mut stale_lines = vec![];
+
+        for (source_name, file) in report.files().iter() {
+            let checksums = self.read_checksums(source_name)?.unwrap_or_default();
+            let mut verified = BTreeSet::new();
+
+            for (_, test) in file.tests().iter() {
+                for (line_number, line) in test.lines().iter() {
+                    let expected = match line.checksum() {
+                        Some(expected) => expected,
+                        None => continue
+                    };
+                    if !verified.insert(*line_number) {
+                        continue;
+                    }
+                    let actual = source_line(&checksums, line_number);
+                    if actual != Some(expected) {
+                        stale_lines.push(StaleLine {
+                            source_file: source_name.clone(),
+                            line_number: *line_number,
+                            expected: expected.clone(),
+                            actual: actual.cloned()
+                        });
+                    }
+                }
+            }
+        }
+        Ok(stale_lines)
+    }
+    /// Returns the checksums of the lines in the source file, None if the source file is not found
+    fn read_checksums(&self, source_name: &str) -> Result<Option<Vec<CheckSum>>> {
+        match fs::read(source_path(self.source_root.as_deref(), source_name)) {
+            Ok(source) => Ok(Some(String::from_utf8_lossy(&source).lines().map(line_checksum).collect())),
+            Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err)
+        }
+    }
+}
+
+/// Returns the checksum of the line, None for the line 0 which does not exist in the source file
+fn source_line<'a>(checksums: &'a [CheckSum], line_number: &LineNumber) -> Option<&'a CheckSum> {
+    (*line_number as usize).checked_sub(1).and_then(|i| checksums.get(i))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() * 4).div_ceil(3));
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0u32, |value, (i, byte)| value | (*byte as u32) << (16 - i * 8));
+        for i in 0..=chunk.len() {
+            encoded.push(BASE64_CHARS[(value >> (18 - i * 6)) as usize & 0x3f] as char);
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use merger:: { merge_files, ReportMerger };
+    use report::summary:: { Summary };
+    use report::checksum:: { SourceChecksum, StaleLine, base64 };
+
+    #[test]
+    fn encode_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg");
+        assert_eq!(base64(b"fo"), "Zm8");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn fill_checksums() {
+        let report = merge_files(&[ "tests/fixtures/exclusion/exclusion.info" ]).unwrap();
+
+        let mut checksum = SourceChecksum::new();
+        checksum.set_source_root("tests/fixtures/exclusion");
+        let filled = checksum.fill(&report).unwrap();
+
+        let test = filled.get("/exclusion.c").unwrap().get_test(&"example".to_string()).unwrap();
+        assert!(test.lines().iter().all(|(_, line)| line.has_checkshum()));
+        assert!(checksum.verify(&filled).unwrap().is_empty());
+
+        let missing = filled.get("/missing.c").unwrap().get_test(&"example".to_string()).unwrap();
+        assert!(!missing.lines().get(&1).unwrap().has_checkshum());
+    }
+
+    #[test]
+    fn verify_stale_lines() {
+        let report = merge_files(&[ "tests/fixtures/merged/ne_checksum/fixture1.info" ]).unwrap();
+
+        let mut checksum = SourceChecksum::new();
+        checksum.set_source_root("tests/fixtures/merged/eq_checksum");
+
+        let stale_lines = checksum.verify(&report).unwrap();
+
+        assert_eq!(stale_lines.len(), 2);
+        assert_eq!(stale_lines[0], StaleLine {
+            source_file: "/fixture1.c".to_string(),
+            line_number: 4,
+            expected: "y7GE3Y4FyXCeXcrtqgSVzw".to_string(),
+            actual: Some("KKXSpItQGXFxwkTeN3OtBw".to_string())
+        });
+    }
+
+    #[test]
+    fn verify_missing_lines() {
+        let report = merge_files(&[ "tests/fixtures/merged/eq_checksum/fixture1.info" ]).unwrap();
+
+        let mut checksum = SourceChecksum::new();
+        checksum.set_source_root("tests/fixtures/merged/ne_checksum");
+        let stale_lines = checksum.verify(&report).unwrap();
+
+        assert_eq!(stale_lines[0].to_string(), "Line not found: /fixture1.c:6, expected: PF4Rz2r7RTliO9u6bZ7h6g");
+        assert!(stale_lines.iter().any(|line| line.source_file == "/func1.c" && line.actual.is_none()));
+    }
+
+    #[test]
+    fn line_zero() {
+        let mut merger = ReportMerger::new();
+        merger.merge_reader("TN:example\nSF:/exclusion.c\nDA:0,1\nDA:1,1\nend_of_record\n".as_bytes()).unwrap();
+
+        let mut checksum = SourceChecksum::new();
+        checksum.set_source_root("tests/fixtures/exclusion");
+        let filled = checksum.fill(&merger.report()).unwrap();
+
+        let test = filled.get("/exclusion.c").unwrap().get_test(&"example".to_string()).unwrap();
+        assert!(!test.lines().get(&0).unwrap().has_checkshum());
+        assert!(test.lines().get(&1).unwrap().has_checkshum());
+
+        let mut merger = ReportMerger::new();
+        merger.merge_reader("TN:example\nSF:/exclusion.c\nDA:0,1,abc\nend_of_record\n".as_bytes()).unwrap();
+        let stale_lines = checksum.verify(&merger.report()).unwrap();
+
+        assert_eq!(stale_lines.len(), 1);
+        assert_eq!(stale_lines[0].line_number, 0);
+        assert_eq!(stale_lines[0].actual, None);
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright (c) 2015-2016 lcov-parser developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::fmt:: { Display, Formatter, Result };
+use std::ops:: { Add, AddAssign };
+use report::counter:: { HitCounter, FoundCounter, HitFoundCounter };
+
+/// Number of the hit and found items
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser::coverage:: { Coverage };
+///
+/// let coverage = Coverage::new(1, 4);
+///
+/// assert_eq!(coverage.percentage(), Some(25.0));
+/// assert_eq!(Coverage::new(0, 0).percentage(), None);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Coverage {
+    hit: usize,
+    found: usize
+}
+
+impl Coverage {
+    pub fn new(hit: usize, found: usize) -> Self {
+        Coverage {
+            hit,
+            found
+        }
+    }
+    /// Returns the percentage of the hit items, or None if nothing was found
+    pub fn percentage(&self) -> Option<f64> {
+        if self.found == 0 {
+            return None;
+        }
+        Some(self.hit as f64 * 100.0 / self.found as f64)
+    }
+}
+
+impl HitCounter for Coverage {
+    fn hit_count(&self) -> usize {
+        self.hit
+    }
+}
+
+impl FoundCounter for Coverage {
+    fn found_count(&self) -> usize {
+        self.found
+    }
+}
+
+impl HitFoundCounter for Coverage {
+}
+
+impl<'a, T: HitFoundCounter> From<&'a T> for Coverage {
+    fn from(counter: &'a T) -> Self {
+        Coverage::new(counter.hit_count(), counter.found_count())
+    }
+}
+
+impl Add for Coverage {
+    type Output = Coverage;
+
+    fn add(self, other: Coverage) -> Coverage {
+        Coverage::new(self.hit + other.hit, self.found + other.found)
+    }
+}
+
+impl AddAssign for Coverage {
+    fn add_assign(&mut self, other: Coverage) {
+        *self = *self + other;
+    }
+}
+
+impl Display for Coverage {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self.percentage() {
+            Some(percentage) => write!(f, "{:.1}% ({} of {})", percentage, self.hit, self.found),
+            None => write!(f, "no data found")
+        }
+    }
+}
+
+/// Coverage of the lines, functions and branches
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CoverageSummary {
+    pub lines: Coverage,
+    pub functions: Coverage,
+    pub branches: Coverage
+}
+
+impl Add for CoverageSummary {
+    type Output = CoverageSummary;
+
+    fn add(self, other: CoverageSummary) -> CoverageSummary {
+        CoverageSummary {
+            lines: self.lines + other.lines,
+            functions: self.functions + other.functions,
+            branches: self.branches + other.branches
+        }
+    }
+}
+
+impl AddAssign for CoverageSummary {
+    fn add_assign(&mut self, other: CoverageSummary) {
+        *self = *self + other;
+    }
+}
+
+/// Same layout as the summary of lcov
+impl Display for CoverageSummary {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        writeln!(f, "lines......: {}", self.lines)?;
+        writeln!(f, "functions..: {}", self.functions)?;
+        writeln!(f, "branches...: {}", self.branches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use report::coverage:: { Coverage, CoverageSummary };
+
+    #[test]
+    fn add_summary() {
+        let mut summary = CoverageSummary {
+            lines: Coverage::new(1, 2),
+            functions: Coverage::new(1, 1),
+            branches: Coverage::default()
+        };
+        summary += CoverageSummary {
+            lines: Coverage::new(2, 2),
+            functions: Coverage::new(0, 1),
+            branches: Coverage::new(1, 4)
+        };
+
+        assert_eq!(summary.lines, Coverage::new(3, 4));
+        assert_eq!(summary.functions.percentage(), Some(50.0));
+        assert_eq!(summary.branches, Coverage::new(1, 4));
+    }
+
+    #[test]
+    fn display() {
+        let summary = CoverageSummary {
+            lines: Coverage::new(1, 3),
+            functions: Coverage::new(1, 1),
+            branches: Coverage::default()
+        };
+        assert_eq!(summary.to_string(), concat!(
+            "lines......: 33.3% (1 of 3)\n",
+            "functions..: 100.0% (1 of 1)\n",
+            "branches...: no data found\n"
+        ));
+    }
+}
@@ -0,0 +1,344 @@
+// Copyright (c) 2015-2016 lcov-parser developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::fmt:: { Display, Formatter, Result };
+use std::collections::btree_map:: { BTreeMap };
+use report:: { Report };
+use report::file:: { File };
+use report::summary:: { Summary };
+use report::attribute:: { SourceFile, LineNumber, FunctionName };
+use report::branch:: { BranchUnit };
+use report::coverage:: { Coverage, CoverageSummary };
+
+/// Difference of the coverage from the base report to the head report
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser:: { merge_files };
+///
+/// let base = merge_files(&[ "tests/fixtures/diff/base.info" ]).unwrap();
+/// let head = merge_files(&[ "tests/fixtures/diff/head.info" ]).unwrap();
+/// let diff = base.diff(&head);
+///
+/// assert_eq!(diff.added_files(), &[ "/src/added.c".to_string() ]);
+/// assert_eq!(diff.files().get("/src/a.c").unwrap().covered_lines(), &[ 3, 11 ]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportDiff {
+    added_files: Vec<SourceFile>,
+    removed_files: Vec<SourceFile>,
+    files: BTreeMap<SourceFile, FileDiff>,
+    base_summary: CoverageSummary,
+    head_summary: CoverageSummary
+}
+
+impl ReportDiff {
+    pub fn new(base: &Report, head: &Report) -> Self {
+        let mut added_files = vec![];
+        let mut removed_files = vec![];
+        let mut files = BTreeMap::new();
+
+        for (source_name, base_file) in base.files().iter() {
+            match head.files().get(source_name) {
+                Some(head_file) => {
+                    let diff = FileDiff::new(base_file, head_file);
+                    if !diff.is_unchanged() {
+                        files.insert(source_name.clone(), diff);
+                    }
+                },
+                None => removed_files.push(source_name.clone())
+            }
+        }
+        for (source_name, _) in head.files().iter() {
+            if !base.files().contains_key(source_name) {
+                added_files.push(source_name.clone());
+            }
+        }
+
+        ReportDiff {
+            added_files,
+            removed_files,
+            files,
+            base_summary: base.coverage_summary(),
+            head_summary: head.coverage_summary()
+        }
+    }
+    /// Files which are only in the head report
+    pub fn added_files(&self) -> &[SourceFile] {
+        &self.added_files
+    }
+    /// Files which are only in the base report
+    pub fn removed_files(&self) -> &[SourceFile] {
+        &self.removed_files
+    }
+    /// Files in both reports whose coverage was changed
+    pub fn files(&self) -> &BTreeMap<SourceFile, FileDiff> {
+        &self.files
+    }
+    pub fn base_summary(&self) -> &CoverageSummary {
+        &self.base_summary
+    }
+    pub fn head_summary(&self) -> &CoverageSummary {
+        &self.head_summary
+    }
+    pub fn delta(&self) -> CoverageDelta {
+        CoverageDelta::new(&self.base_summary, &self.head_summary)
+    }
+    /// Returns true if the coverage was not changed
+    pub fn is_unchanged(&self) -> bool {
+        self.added_files.is_empty() && self.removed_files.is_empty() && self.files.is_empty()
+    }
+}
+
+impl Display for ReportDiff {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        for source_name in self.added_files.iter() {
+            writeln!(f, "added: {}", source_name)?;
+        }
+        for source_name in self.removed_files.iter() {
+            writeln!(f, "removed: {}", source_name)?;
+        }
+        for (source_name, diff) in self.files.iter() {
+            writeln!(f, "changed: {}", source_name)?;
+            write!(f, "{}", diff)?;
+        }
+        write_delta(f, &self.base_summary, &self.head_summary)
+    }
+}
+
+/// Difference of the file in both reports.
+/// The hit status is compared for the lines, functions and branches which are in both reports,
+/// and the items hit by any test of the file are hit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDiff {
+    covered_lines: Vec<LineNumber>,
+    uncovered_lines: Vec<LineNumber>,
+    covered_functions: Vec<FunctionName>,
+    uncovered_functions: Vec<FunctionName>,
+    covered_branches: Vec<(LineNumber, BranchUnit)>,
+    uncovered_branches: Vec<(LineNumber, BranchUnit)>,
+    base_summary: CoverageSummary,
+    head_summary: CoverageSummary
+}
+
+impl FileDiff {
+    pub fn new(base: &File, head: &File) -> Self {
+        let base_hits = base.hits();
+        let head_hits = head.hits();
+        let (covered_lines, uncovered_lines) = flipped(&base_hits.lines, &head_hits.lines, |line_number| **line_number);
+        let (covered_functions, uncovered_functions) = flipped(&base_hits.functions, &head_hits.functions, |name| (*name).clone());
+        let (covered_branches, uncovered_branches) = flipped(&base_hits.branches, &head_hits.branches, |&(line_number, unit)| {
+            (*line_number, unit.clone())
+        });
+
+        FileDiff {
+            covered_lines,
+            uncovered_lines,
+            covered_functions,
+            uncovered_functions,
+            covered_branches,
+            uncovered_branches,
+            base_summary: base.coverage_summary(),
+            head_summary: head.coverage_summary()
+        }
+    }
+    /// Lines which were not hit in the base report, and are hit in the head report
+    pub fn covered_lines(&self) -> &[LineNumber] {
+        &self.covered_lines
+    }
+    /// Lines which were hit in the base report, and are not hit in the head report
+    pub fn uncovered_lines(&self) -> &[LineNumber] {
+        &self.uncovered_lines
+    }
+    pub fn covered_functions(&self) -> &[FunctionName] {
+        &self.covered_functions
+    }
+    pub fn uncovered_functions(&self) -> &[FunctionName] {
+        &self.uncovered_functions
+    }
+    pub fn covered_branches(&self) -> &[(LineNumber, BranchUnit)] {
+        &self.covered_branches
+    }
+    pub fn uncovered_branches(&self) -> &[(LineNumber, BranchUnit)] {
+        &self.uncovered_branches
+    }
+    pub fn base_summary(&self) -> &CoverageSummary {
+        &self.base_summary
+    }
+    pub fn head_summary(&self) -> &CoverageSummary {
+        &self.head_summary
+    }
+    pub fn delta(&self) -> CoverageDelta {
+        CoverageDelta::new(&self.base_summary, &self.head_summary)
+    }
+    /// Returns true if the hit status and the coverage were not changed
+    pub fn is_unchanged(&self) -> bool {
+        self.covered_lines.is_empty() && self.uncovered_lines.is_empty() &&
+        self.covered_functions.is_empty() && self.uncovered_functions.is_empty() &&
+        self.covered_branches.is_empty() && self.uncovered_branches.is_empty() &&
+        self.base_summary == self.head_summary
+    }
+}
+
+impl Display for FileDiff {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let line_numbers = |lines: &[LineNumber]| lines.iter().map(|line| line.to_string()).collect::<Vec<_>>().join(", ");
+        let branches = |branches: &[(LineNumber, BranchUnit)]| {
+            branches.iter().map(|(line, unit)| format!("{}:{}", line, unit)).collect::<Vec<_>>().join(", ")
+        };
+
+        if !self.covered_lines.is_empty() {
+            writeln!(f, "  covered lines: {}", line_numbers(&self.covered_lines))?;
+        }
+        if !self.uncovered_lines.is_empty() {
+            writeln!(f, "  uncovered lines: {}", line_numbers(&self.uncovered_lines))?;
+        }
+        if !self.covered_functions.is_empty() {
+            writeln!(f, "  covered functions: {}", self.covered_functions.join(", "))?;
+        }
+        if !self.uncovered_functions.is_empty() {
+            writeln!(f, "  uncovered functions: {}", self.uncovered_functions.join(", "))?;
+        }
+        if !self.covered_branches.is_empty() {
+            writeln!(f, "  covered branches: {}", branches(&self.covered_branches))?;
+        }
+        if !self.uncovered_branches.is_empty() {
+            writeln!(f, "  uncovered branches: {}", branches(&self.uncovered_branches))?;
+        }
+        Ok(())
+    }
+}
+
+/// Differences of the percentages from the base to the head, None if either has no data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoverageDelta {
+    pub lines: Option<f64>,
+    pub functions: Option<f64>,
+    pub branches: Option<f64>
+}
+
+impl CoverageDelta {
+    pub fn new(base: &CoverageSummary, head: &CoverageSummary) -> Self {
+        CoverageDelta {
+            lines: percentage_delta(&base.lines, &head.lines),
+            functions: percentage_delta(&base.functions, &head.functions),
+            branches: percentage_delta(&base.branches, &head.branches)
+        }
+    }
+}
+
+fn percentage_delta(base: &Coverage, head: &Coverage) -> Option<f64> {
+    match (base.percentage(), head.percentage()) {
+        (Some(base), Some(head)) => Some(head - base),
+        _ => None
+    }
+}
+
+fn flipped<K: Ord, T, F>(base: &BTreeMap<K, bool>, head: &BTreeMap<K, bool>, to_owned: F) -> (Vec<T>, Vec<T>)
+    where F: Fn(&K) -> T
+{
+    let mut covered = vec![];
+    let mut uncovered = vec![];
+
+    for (key, base_hit) in base.iter() {
+        match (*base_hit, head.get(key)) {
+            (false, Some(&true)) => covered.push(to_owned(key)),
+            (true, Some(&false)) => uncovered.push(to_owned(key)),
+            _ => {}
+        }
+    }
+    (covered, uncovered)
+}
+
+fn write_delta(f: &mut Formatter, base: &CoverageSummary, head: &CoverageSummary) -> Result {
+    let delta = CoverageDelta::new(base, head);
+    let items = [
+        ("lines......", &base.lines, &head.lines, delta.lines),
+        ("functions..", &base.functions, &head.functions, delta.functions),
+        ("branches...", &base.branches, &head.branches, delta.branches)
+    ];
+    for &(label, base, head, delta) in items.iter() {
+        match delta {
+            Some(delta) => writeln!(f, "{}: {} -> {} ({:+.1}%)", label, base, head, delta)?,
+            None => writeln!(f, "{}: {} -> {}", label, base, head)?
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use merger:: { merge_files };
+    use report::branch:: { BranchUnit };
+    use report::coverage:: { Coverage };
+    use report::diff:: { ReportDiff };
+
+    fn diff_fixture() -> ReportDiff {
+        let base = merge_files(&[ "tests/fixtures/diff/base.info" ]).unwrap();
+        let head = merge_files(&[ "tests/fixtures/diff/head.info" ]).unwrap();
+        base.diff(&head)
+    }
+
+    #[test]
+    fn diff_files() {
+        let diff = diff_fixture();
+
+        assert_eq!(diff.added_files(), &[ "/src/added.c".to_string() ]);
+        assert_eq!(diff.removed_files(), &[ "/src/removed.c".to_string() ]);
+        assert_eq!(diff.files().keys().collect::<Vec<_>>(), vec![ "/src/a.c" ]);
+        assert!(!diff.is_unchanged());
+    }
+
+    #[test]
+    fn diff_hit_status() {
+        let diff = diff_fixture();
+        let file = diff.files().get("/src/a.c").unwrap();
+
+        assert_eq!(file.covered_lines(), &[ 3, 11 ]);
+        assert_eq!(file.uncovered_lines(), &[ 2 ]);
+        assert_eq!(file.covered_functions(), &[ "b".to_string() ]);
+        assert_eq!(file.uncovered_functions(), &[ "a".to_string() ]);
+        assert_eq!(file.covered_branches(), &[ (3, BranchUnit::new(0, 1)) ]);
+        assert!(file.uncovered_branches().is_empty());
+        assert_eq!(file.head_summary().lines, Coverage::new(3, 4));
+    }
+
+    #[test]
+    fn diff_delta() {
+        let diff = diff_fixture();
+        let delta = diff.delta();
+
+        assert_eq!(delta.lines, Some(10.0));
+        assert_eq!(delta.functions, Some(0.0));
+        assert_eq!(delta.branches, Some(50.0));
+        assert_eq!(diff.to_string(), concat!(
+            "added: /src/added.c\n",
+            "removed: /src/removed.c\n",
+            "changed: /src/a.c\n",
+            "  covered lines: 3, 11\n",
+            "  uncovered lines: 2\n",
+            "  covered functions: b\n",
+            "  uncovered functions: a\n",
+            "  covered branches: 3:0-1\n",
+            "lines......: 50.0% (2 of 4) -> 60.0% (3 of 5) (+10.0%)\n",
+            "functions..: 50.0% (1 of 2) -> 50.0% (1 of 2) (+0.0%)\n",
+            "branches...: 50.0% (1 of 2) -> 100.0% (2 of 2) (+50.0%)\n"
+        ));
+    }
+
+    #[test]
+    fn unchanged() {
+        let base = merge_files(&[ "tests/fixtures/diff/base.info" ]).unwrap();
+        let diff = base.diff(&base);
+
+        assert!(diff.is_unchanged());
+        assert_eq!(diff.delta().lines, Some(0.0));
+        assert!(diff.files().is_empty());
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright (c) 2015-2016 lcov-parser developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::fs;
+use std::io:: { Result, ErrorKind };
+use std::path:: { PathBuf };
+use std::collections:: { BTreeSet };
+use report:: { Report, source_path };
+use report::test:: { Test };
+use report::attribute:: { LineNumber };
+
+/// Markers of the exclusion in the source file, the line is matched if it contains the marker
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExclusionMarkers {
+    /// Exclude the line, `LCOV_EXCL_LINE`
+    pub line: Vec<String>,
+    /// Start of the excluded lines, `LCOV_EXCL_START`
+    pub start: Vec<String>,
+    /// End of the excluded lines, `LCOV_EXCL_STOP`
+    pub stop: Vec<String>,
+    /// Exclude the branches on the line, `LCOV_EXCL_BR_LINE`
+    pub branch_line: Vec<String>,
+    /// Start of the lines whose branches are excluded, `LCOV_EXCL_BR_START`
+    pub branch_start: Vec<String>,
+    /// End of the lines whose branches are excluded, `LCOV_EXCL_BR_STOP`
+    pub branch_stop: Vec<String>
+}
+
+impl Default for ExclusionMarkers {
+    fn default() -> Self {
+        ExclusionMarkers {
+            line: vec![ "LCOV_EXCL_LINE".to_string() ],
+            start: vec![ "LCOV_EXCL_START".to_string() ],
+            stop: vec![ "LCOV_EXCL_STOP".to_string() ],
+            branch_line: vec![ "LCOV_EXCL_BR_LINE".to_string() ],
+            branch_start: vec![ "LCOV_EXCL_BR_START".to_string() ],
+            branch_stop: vec![ "LCOV_EXCL_BR_STOP".to_string() ]
+        }
+    }
+}
+
+/// Remove the lines, branches and functions excluded by the markers in the source files, like lcov and genhtml.
+///
+/// The excluded lines also remove the branches on the lines and the functions starting on the lines.
+/// The source file which is not found is kept as it is.
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser:: { merge_files };
+/// use lcov_parser::exclusion:: { SourceExclusion };
+/// use lcov_parser::summary:: { Summary };
+///
+/// let report = merge_files(&[ "tests/fixtures/exclusion/exclusion.info" ]).unwrap();
+///
+/// let mut exclusion = SourceExclusion::new();
+/// exclusion.set_source_root("tests/fixtures/exclusion");
+/// let excluded = exclusion.apply(&report).unwrap();
+///
+/// let test = excluded.get("/exclusion.c").unwrap().get_test(&"example".to_string()).unwrap();
+/// assert!(test.lines().get(&4).is_none());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SourceExclusion {
+    source_root: Option<PathBuf>,
+    markers: ExclusionMarkers
+}
+
+impl SourceExclusion {
+    pub fn new() -> Self {
+        SourceExclusion {
+            source_root: None,
+            markers: ExclusionMarkers::default()
+        }
+    }
+    /// Set the directory of the source files which have the markers, the path of the report is used if the root is not set
+    pub fn set_source_root<P: Into<PathBuf>>(&mut self, source_root: P) {
+        self.source_root = Some(source_root.into());
+    }
+    /// Set the markers, the markers of lcov are used by default
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser::exclusion:: { SourceExclusion, ExclusionMarkers };
+    ///
+    /// let mut markers = ExclusionMarkers::default();
+    /// markers.line.push("GCOVR_EXCL_LINE".to_string());
+    ///
+    /// let mut exclusion = SourceExclusion::new();
+    /// exclusion.set_markers(markers);
+    /// ```
+    pub fn set_markers(&mut self, markers: ExclusionMarkers) {
+        self.markers = markers;
+    }
+    pub fn apply(&self, report: &Report) -> Result<Report> {
+        report.map_tests(|source_name| {
+            let source = match fs::read(source_path(self.source_root.as_deref(), source_name)) {
+                Ok(source) => String::from_utf8_lossy(&source).into_owned(),
+                Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+                Err(err) => return Err(err)
+            };
+            let excluded = ExcludedLines::scan(&source, &self.markers);
+
+            Ok(Some(move |test: &mut Test| {
+                test.exclude_lines(|line_number| excluded.lines.contains(line_number));
+                test.exclude_branches(|line_number| excluded.branches.contains(line_number));
+            }))
+        })
+    }
+}
+
+struct ExcludedLines {
+    lines: BTreeSet<LineNumber>,
+    branches: BTreeSet<LineNumber>
+}
+
+impl ExcludedLines {
+    fn scan(source: &str, markers: &ExclusionMarkers) -> Self {
+        let contains = |text: &str, markers: &[String]| markers.iter().any(|marker| text.contains(marker.as_str()));
+        let mut lines = BTreeSet::new();
+        let mut branches = BTreeSet::new();
+        let mut in_lines = false;
+        let mut in_branches = false;
+
+        for (index, text) in source.lines().enumerate() {
+            let line_number = index as LineNumber + 1;
+
+            if contains(text, &markers.start) {
+                in_lines = true;
+            }
+            if in_lines || contains(text, &markers.line) {
+                lines.insert(line_number);
+            }
+            if contains(text, &markers.stop) {
+                in_lines = false;
+            }
+
+            if contains(text, &markers.branch_start) {
+                in_branches = true;
+            }
+            if in_branches || contains(text, &markers.branch_line) {
+                branches.insert(line_number);
+            }
+            if contains(text, &markers.branch_stop) {
+                in_branches = false;
+            }
+        }
+        ExcludedLines { lines, branches }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use merger:: { merge_files };
+    use report::summary:: { Summary };
+    use report::exclusion:: { SourceExclusion, ExclusionMarkers, ExcludedLines };
+
+    #[test]
+    fn scan_markers() {
+        let source = concat!(
+            "int a; // LCOV_EXCL_LINE\n",
+            "int b;\n",
+            "/* LCOV_EXCL_START */\n",
+            "int c;\n",
+            "/* LCOV_EXCL_STOP */\n",
+            "if (d) {} // LCOV_EXCL_BR_LINE\n",
+            "// LCOV_EXCL_BR_START\n",
+            "if (e) {}\n",
+            "// LCOV_EXCL_BR_STOP\n",
+            "if (f) {}\n"
+        );
+        let excluded = ExcludedLines::scan(source, &ExclusionMarkers::default());
+
+        assert_eq!(excluded.lines.iter().cloned().collect::<Vec<_>>(), vec![ 1, 3, 4, 5 ]);
+        assert_eq!(excluded.branches.iter().cloned().collect::<Vec<_>>(), vec![ 6, 7, 8, 9 ]);
+    }
+
+    #[test]
+    fn apply_to_report() {
+        let report = merge_files(&[ "tests/fixtures/exclusion/exclusion.info" ]).unwrap();
+
+        let mut exclusion = SourceExclusion::new();
+        exclusion.set_source_root("tests/fixtures/exclusion");
+        let excluded = exclusion.apply(&report).unwrap();
+
+        let test = excluded.get("/exclusion.c").unwrap().get_test(&"example".to_string()).unwrap();
+        let lines: Vec<_> = test.lines().iter().map(|(line_number, _)| *line_number).collect();
+        let branches: Vec<_> = test.branches().iter().map(|(line_number, _)| *line_number).collect();
+        let functions: Vec<_> = test.functions().iter().map(|(name, _)| name.as_str()).collect();
+
+        assert_eq!(lines, vec![ 3, 5, 12, 13, 14, 15 ]);
+        assert_eq!(branches, vec![ 14 ]);
+        assert_eq!(functions, vec![ "check", "main" ]);
+
+        let missing = excluded.get("/missing.c").unwrap().get_test(&"example".to_string()).unwrap();
+        assert_eq!(missing.lines().len(), 1);
+    }
+
+    #[test]
+    fn custom_markers() {
+        let report = merge_files(&[ "tests/fixtures/exclusion/exclusion.info" ]).unwrap();
+        let mut markers = ExclusionMarkers::default();
+        markers.line.push("NO_COVERAGE".to_string());
+
+        let mut exclusion = SourceExclusion::new();
+        exclusion.set_source_root("tests/fixtures/exclusion");
+        exclusion.set_markers(markers);
+        let excluded = exclusion.apply(&report).unwrap();
+
+        let test = excluded.get("/exclusion.c").unwrap().get_test(&"example".to_string()).unwrap();
+        assert!(test.lines().get(&15).is_none());
+    }
+}
@@ -10,9 +10,13 @@ use std::collections::btree_map:: { BTreeMap };
 use merger::ops:: { TryMerge, MergeResult, TestError };
 use report::test:: { Test, Tests };
 use report::summary:: { Summary };
-use report::attribute:: { SourceFile };
+use report::attribute:: { SourceFile, LineNumber, FunctionName };
+use report::branch:: { BranchUnit };
+use report::counter:: { Hit };
+use report::coverage:: { Coverage, CoverageSummary };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct File {
     tests: Tests
 }
@@ -20,7 +24,7 @@ pub struct File {
 impl File {
     pub fn new(tests: Tests) -> Self {
         File {
-            tests: tests
+            tests
         }
     }
     pub fn tests(&self) -> &Tests {
@@ -29,6 +33,58 @@ impl File {
     pub fn get_test(&self, name: &String) -> Option<&Test> {
         self.tests.get(name)
     }
+    pub(crate) fn get_test_mut(&mut self, name: &String) -> Option<&mut Test> {
+        self.tests.get_mut(name)
+    }
+    /// Returns the lines, functions and branches of all tests with the summed execution counts
+    pub fn combined(&self) -> Result<Test, TestError> {
+        let mut combined = Test::new();
+        for (_, test) in self.tests.iter() {
+            combined.try_merge(test)?;
+        }
+        Ok(combined)
+    }
+    /// Returns the coverage of all tests.
+    /// The line hit by several tests is counted once.
+    pub fn coverage_summary(&self) -> CoverageSummary {
+        let hits = self.hits();
+        CoverageSummary {
+            lines: union_coverage(&hits.lines),
+            functions: union_coverage(&hits.functions),
+            branches: union_coverage(&hits.branches)
+        }
+    }
+    /// Returns whether the lines, functions and branches are hit by any test
+    pub(crate) fn hits(&self) -> FileHits<'_> {
+        let mut hits = FileHits::default();
+
+        for (_, test) in self.tests.iter() {
+            for (line_number, line) in test.lines().iter() {
+                *hits.lines.entry(line_number).or_insert(false) |= line.is_hit();
+            }
+            for (name, function) in test.functions().iter() {
+                *hits.functions.entry(name).or_insert(false) |= function.is_hit();
+            }
+            for (line_number, blocks) in test.branches().iter() {
+                for (unit, branch) in blocks.iter() {
+                    *hits.branches.entry((line_number, unit)).or_insert(false) |= branch.is_hit();
+                }
+            }
+        }
+        hits
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct FileHits<'a> {
+    pub lines: BTreeMap<&'a LineNumber, bool>,
+    pub functions: BTreeMap<&'a FunctionName, bool>,
+    pub branches: BTreeMap<(&'a LineNumber, &'a BranchUnit), bool>
+}
+
+fn union_coverage<K>(items: &BTreeMap<K, bool>) -> Coverage {
+    let hit = items.values().filter(|hit| **hit).count();
+    Coverage::new(hit, items.len())
 }
 
 impl<'a> TryMerge<&'a File> for File {
@@ -42,16 +98,30 @@ impl<'a> TryMerge<&'a File> for File {
 
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct Files {
     files: BTreeMap<SourceFile, File>
 }
 
+impl Default for Files {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Files {
     pub fn new() -> Self {
         Files {
             files: BTreeMap::new()
         }
     }
+    /// Keep the files which the predicate returns true for
+    pub fn retain<F: FnMut(&SourceFile) -> bool>(&mut self, mut predicate: F) {
+        self.files.retain(|source_name, _| predicate(source_name));
+    }
+    pub(crate) fn get_mut(&mut self, source_name: &SourceFile) -> Option<&mut File> {
+        self.files.get_mut(source_name)
+    }
 }
 
 impl_summary!(Files, files<SourceFile, File>);
@@ -71,3 +141,65 @@ impl<'a> TryMerge<(&'a SourceFile, &'a File)> for Files {
 }
 
 impl_try_merge_self_summary!(Files:files, TestError);
+
+
+#[cfg(test)]
+mod tests {
+    use merger::ops::*;
+    use record:: { LineData, FunctionName, FunctionData, BranchData };
+    use report::test:: { Tests };
+    use report::file:: { File };
+    use report::coverage:: { Coverage };
+    use report::line:: { Line };
+    use report::summary:: { Summary };
+
+    #[test]
+    fn combined() {
+        let mut tests = Tests::new();
+        let test1 = "test1".to_string();
+        let test2 = "test2".to_string();
+
+        tests.try_merge((&test1, &LineData { line: 1, count: 1, checksum: None })).unwrap();
+        tests.try_merge((&test1, &LineData { line: 2, count: 0, checksum: None })).unwrap();
+        tests.try_merge((&test2, &LineData { line: 1, count: 3, checksum: Some("abc".to_string()) })).unwrap();
+        tests.try_merge((&test2, &FunctionName { name: "main".to_string(), line: 1, end: None })).unwrap();
+        tests.try_merge((&test2, &FunctionData { name: "main".to_string(), count: 2 })).unwrap();
+
+        let file = File::new(tests);
+        let combined = file.combined().unwrap();
+
+        assert_eq!(combined.lines().get(&1), Some(&Line::new(1, 4, Some("abc".to_string()))));
+        assert_eq!(combined.lines().get(&2), Some(&Line::new(2, 0, None)));
+        assert_eq!(combined.functions().get(&"main".to_string()).unwrap().execution_count(), &2);
+    }
+
+    #[test]
+    fn coverage_summary() {
+        let mut tests = Tests::new();
+        let test1 = "test1".to_string();
+        let test2 = "test2".to_string();
+
+        tests.try_merge((&test1, &LineData { line: 1, count: 1, checksum: None })).unwrap();
+        tests.try_merge((&test1, &LineData { line: 2, count: 0, checksum: None })).unwrap();
+        tests.try_merge((&test2, &LineData { line: 1, count: 3, checksum: None })).unwrap();
+        tests.try_merge((&test2, &LineData { line: 2, count: 0, checksum: None })).unwrap();
+        tests.try_merge((&test2, &LineData { line: 3, count: 1, checksum: None })).unwrap();
+        tests.try_merge((&test1, &FunctionName { name: "main".to_string(), line: 1, end: None })).unwrap();
+        tests.try_merge((&test1, &FunctionData { name: "main".to_string(), count: 0 })).unwrap();
+        tests.try_merge((&test2, &FunctionName { name: "main".to_string(), line: 1, end: None })).unwrap();
+        tests.try_merge((&test2, &FunctionData { name: "main".to_string(), count: 1 })).unwrap();
+        tests.try_merge((&test1, &BranchData { line: 1, block: 0, branch: 0, taken: Some(1) })).unwrap();
+        tests.try_merge((&test2, &BranchData { line: 1, block: 0, branch: 1, taken: None })).unwrap();
+
+        let file = File::new(tests);
+        let summary = file.coverage_summary();
+
+        assert_eq!(summary.lines, Coverage::new(2, 3));
+        assert_eq!(summary.functions, Coverage::new(1, 1));
+        assert_eq!(summary.branches, Coverage::new(1, 2));
+
+        let test_summary = file.get_test(&test1).unwrap().coverage_summary();
+        assert_eq!(test_summary.lines, Coverage::new(1, 2));
+        assert_eq!(test_summary.functions, Coverage::new(0, 1));
+    }
+}
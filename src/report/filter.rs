@@ -0,0 +1,126 @@
+// Copyright (c) 2015-2016 lcov-parser developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+extern crate glob;
+
+use std::fmt;
+use std::error:: { Error };
+use self::glob:: { Pattern, MatchOptions };
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false
+};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FilterError {
+    pattern: String,
+    message: String
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid pattern: {}, {}", self.pattern, self.message)
+    }
+}
+
+impl Error for FilterError {
+}
+
+/// Filter of the source files by the glob patterns, like `lcov --extract` and `lcov --remove`.
+/// `*` matches the characters except `/`, and `**` matches the directories.
+///
+/// The source file is kept if it matches any of the include patterns, or no include pattern is added,
+/// and it matches none of the exclude patterns.
+///
+/// # Examples
+///
+/// ```
+/// use lcov_parser::filter:: { FileFilter };
+///
+/// let mut filter = FileFilter::new();
+/// filter.exclude("/usr/include/**").unwrap();
+/// filter.exclude("**/*.pb.cc").unwrap();
+///
+/// assert!(filter.is_match("/src/main.c"));
+/// assert!(!filter.is_match("/usr/include/stdio.h"));
+/// assert!(!filter.is_match("/src/proto/message.pb.cc"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    includes: Vec<Pattern>,
+    excludes: Vec<Pattern>
+}
+
+impl FileFilter {
+    pub fn new() -> Self {
+        FileFilter {
+            includes: vec![],
+            excludes: vec![]
+        }
+    }
+    /// Add the pattern of the source files to keep
+    pub fn include(&mut self, pattern: &str) -> Result<(), FilterError> {
+        self.includes.push(compile(pattern)?);
+        Ok(())
+    }
+    /// Add the pattern of the source files to remove
+    pub fn exclude(&mut self, pattern: &str) -> Result<(), FilterError> {
+        self.excludes.push(compile(pattern)?);
+        Ok(())
+    }
+    /// Returns true if the source file is kept
+    pub fn is_match(&self, source_name: &str) -> bool {
+        let included = self.includes.is_empty() ||
+            self.includes.iter().any(|pattern| pattern.matches_with(source_name, MATCH_OPTIONS));
+        included && !self.excludes.iter().any(|pattern| pattern.matches_with(source_name, MATCH_OPTIONS))
+    }
+}
+
+fn compile(pattern: &str) -> Result<Pattern, FilterError> {
+    Pattern::new(pattern).map_err(|err| FilterError {
+        pattern: pattern.to_string(),
+        message: err.msg.to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use report::filter:: { FileFilter };
+
+    #[test]
+    fn include_and_exclude() {
+        let mut filter = FileFilter::new();
+        filter.include("/src/**").unwrap();
+        filter.exclude("/src/third_party/**").unwrap();
+
+        assert!(filter.is_match("/src/main.c"));
+        assert!(filter.is_match("/src/lib/lib.c"));
+        assert!(!filter.is_match("/src/third_party/zlib/zlib.c"));
+        assert!(!filter.is_match("/usr/include/stdio.h"));
+    }
+
+    #[test]
+    fn wildcard() {
+        let mut filter = FileFilter::new();
+        filter.include("/src/*.c").unwrap();
+
+        assert!(filter.is_match("/src/main.c"));
+        assert!(!filter.is_match("/src/lib/lib.c"));
+        assert!(!filter.is_match("/src/main.h"));
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let mut filter = FileFilter::new();
+        let err = filter.exclude("/src/[").unwrap_err();
+        assert!(err.to_string().starts_with("Invalid pattern: /src/["));
+        assert!(FileFilter::new().is_match("/src/main.c"));
+    }
+}
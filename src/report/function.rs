@@ -10,8 +10,9 @@ use std::io;
 use std::cmp::PartialEq;
 use std::collections::btree_map:: { BTreeMap };
 use std::convert::{ From };
+use std::ops:: { RangeInclusive };
 use std::fmt:: { Display, Formatter, Result };
-use record:: { FunctionName as FunctionNameRecord, FunctionData };
+use record:: { FunctionName as FunctionNameRecord, FunctionData, FunctionLine, FunctionAlias };
 use merger::ops:: { TryMerge, MergeResult, FunctionError };
 use record:: { RecordWrite };
 use report::summary:: { Summary };
@@ -19,16 +20,86 @@ use report::attribute:: { ExecutionCount, FunctionName, LineNumber };
 use report::counter:: { Hit, HitFoundCounter, FoundCounter, HitCounter };
 
 
+/// Format of the function records
+///
+/// `Legacy` writes FN / FNDA records, `Alias` writes the FNL / FNA records of LCOV 2.x.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+pub enum FunctionFormat {
+    #[default]
+    Legacy,
+    Alias
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct Functions {
-    functions: BTreeMap<FunctionName, Function>
+    functions: BTreeMap<FunctionName, Function>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    indexes: BTreeMap<u32, FunctionLine>
+}
+
+impl Default for Functions {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Functions {
     pub fn new() -> Self {
         Functions {
-            functions: BTreeMap::new()
+            functions: BTreeMap::new(),
+            indexes: BTreeMap::new()
+        }
+    }
+
+    /// Returns the functions sharing the line range of the given function, including itself.
+    pub fn aliases(&self, name: &FunctionName) -> Vec<&Function> {
+        let function = match self.functions.get(name) {
+            Some(function) => function,
+            None => return vec![]
+        };
+        self.functions.values()
+            .filter(|other| other.alias_key() == function.alias_key())
+            .collect()
+    }
+    /// Keep the functions which the predicate returns true for
+    pub fn retain<F: FnMut(&Function) -> bool>(&mut self, mut predicate: F) {
+        self.functions.retain(|_, function| predicate(function));
+    }
+    pub fn display_as(&self, format: FunctionFormat) -> FunctionsDisplay<'_> {
+        FunctionsDisplay {
+            functions: self,
+            format
+        }
+    }
+    fn alias_groups(&self) -> BTreeMap<(LineNumber, Option<LineNumber>), Vec<&Function>> {
+        let mut groups: BTreeMap<_, Vec<&Function>> = BTreeMap::new();
+        for function in self.functions.values() {
+            groups.entry(function.alias_key()).or_default().push(function);
         }
+        groups
+    }
+    fn fmt_aliases(&self, f: &mut Formatter) -> Result {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let groups = self.alias_groups();
+        for (index, (&(start, end), functions)) in groups.iter().enumerate() {
+            match end {
+                Some(end) => writeln!(f, "FNL:{},{},{}", index, start, end)?,
+                None => writeln!(f, "FNL:{},{}", index, start)?
+            }
+            for function in functions.iter() {
+                writeln!(f, "FNA:{},{},{}", index, function.execution_count(), function.name())?;
+            }
+        }
+        let hit_count = groups.values()
+            .filter(|functions| functions.iter().any(|function| function.is_hit()))
+            .count();
+        writeln!(f, "FNF:{}", groups.len())?;
+        writeln!(f, "FNH:{}", hit_count)?;
+        Ok(())
     }
 }
 
@@ -64,15 +135,32 @@ impl Display for Functions {
             return Ok(());
         }
         for (_, function) in self.iter() {
-            writeln!(f, "FN:{},{}", function.line_number(), function.name())?;
+            match function.end_line_number() {
+                Some(end_line_number) => writeln!(f, "FN:{},{},{}", function.line_number(), end_line_number, function.name())?,
+                None => writeln!(f, "FN:{},{}", function.line_number(), function.name())?
+            }
             writeln!(f, "FNDA:{},{}", function.execution_count(), function.name())?;
         }
-        writeln!(f, "FNF:{}", self.hit_count())?;
-        writeln!(f, "FNH:{}", self.found_count())?;
+        writeln!(f, "FNF:{}", self.found_count())?;
+        writeln!(f, "FNH:{}", self.hit_count())?;
         Ok(())
     }
 }
 
+pub struct FunctionsDisplay<'a> {
+    functions: &'a Functions,
+    format: FunctionFormat
+}
+
+impl<'a> Display for FunctionsDisplay<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self.format {
+            FunctionFormat::Legacy => write!(f, "{}", self.functions),
+            FunctionFormat::Alias => self.functions.fmt_aliases(f)
+        }
+    }
+}
+
 
 
 impl<'a> TryMerge<&'a FunctionData> for Functions {
@@ -108,13 +196,40 @@ impl<'a> TryMerge<&'a FunctionNameRecord> for Functions {
     }
 }
 
+impl<'a> TryMerge<&'a FunctionLine> for Functions {
+    type Err = FunctionError;
+
+    fn try_merge(&mut self, function_line: &'a FunctionLine) -> MergeResult<Self::Err> {
+        self.indexes.insert(function_line.index, *function_line);
+        Ok(())
+    }
+}
+
+impl<'a> TryMerge<&'a FunctionAlias> for Functions {
+    type Err = FunctionError;
+
+    fn try_merge(&mut self, function_alias: &'a FunctionAlias) -> MergeResult<Self::Err> {
+        let function = match self.indexes.get(&function_alias.index) {
+            Some(function_line) => Function::from((function_line, function_alias)),
+            None => return Err(FunctionError::UnknownIndex(function_alias.index))
+        };
+        if let Some(current) = self.functions.get_mut(&function_alias.name) {
+            return current.try_merge(&function);
+        }
+        self.functions.insert(function_alias.name.clone(), function);
+        Ok(())
+    }
+}
+
 impl_try_merge_self_summary!(Functions:functions, FunctionError);
 
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Function {
     name: FunctionName,
     line_number: LineNumber,
+    end_line_number: Option<LineNumber>,
     execution_count: ExecutionCount
 }
 
@@ -125,9 +240,10 @@ impl Function {
         execution_count: ExecutionCount,
     ) -> Self {
         Function {
-            name: name,
-            line_number: line_number,
-            execution_count: execution_count
+            name,
+            line_number,
+            end_line_number: None,
+            execution_count
         }
     }
     pub fn name(&self) -> &FunctionName {
@@ -136,12 +252,31 @@ impl Function {
     pub fn line_number(&self) -> &LineNumber {
         &self.line_number
     }
+    pub fn end_line_number(&self) -> Option<&LineNumber> {
+        self.end_line_number.as_ref()
+    }
     pub fn execution_count(&self) -> &ExecutionCount {
         &self.execution_count
     }
     pub fn is_hit(&self) -> bool {
         self.execution_count.is_hit()
     }
+    /// Returns the lines from the start to the end of the function,
+    /// if the end line is known and is not before the start line
+    pub fn line_range(&self) -> Option<RangeInclusive<LineNumber>> {
+        self.end_line_number
+            .filter(|end_line_number| *end_line_number >= self.line_number)
+            .map(|end_line_number| self.line_number..=end_line_number)
+    }
+    pub fn contains_line(&self, line_number: &LineNumber) -> bool {
+        match self.line_range() {
+            Some(range) => range.contains(line_number),
+            None => false
+        }
+    }
+    fn alias_key(&self) -> (LineNumber, Option<LineNumber>) {
+        (self.line_number, self.end_line_number)
+    }
 }
 
 impl<'a> From<&'a FunctionData> for Function {
@@ -157,11 +292,24 @@ impl<'a> From<&'a FunctionData> for Function {
 
 impl<'a> From<&'a FunctionNameRecord> for Function {
     fn from(function_name: &'a FunctionNameRecord) -> Self {
-        Function::new(
-            function_name.name.clone(),
-            function_name.line,
-            0
-        )
+        Function {
+            name: function_name.name.clone(),
+            line_number: function_name.line,
+            end_line_number: function_name.end,
+            execution_count: 0
+        }
+    }
+}
+
+impl<'a> From<(&'a FunctionLine, &'a FunctionAlias)> for Function {
+    fn from(function: (&'a FunctionLine, &'a FunctionAlias)) -> Self {
+        let (function_line, function_alias) = function;
+        Function {
+            name: function_alias.name.clone(),
+            line_number: function_line.start,
+            end_line_number: function_line.end,
+            execution_count: function_alias.count
+        }
     }
 }
 
@@ -183,6 +331,9 @@ impl<'a> TryMerge<&'a FunctionNameRecord> for Function {
         }
         self.name = other.name.clone();
         self.line_number = other.line;
+        if other.end.is_some() {
+            self.end_line_number = other.end;
+        }
         Ok(())
     }
 }
@@ -199,7 +350,7 @@ impl<'a> TryMerge<&'a FunctionData> for Function {
                 other.name.clone()
             ));
         }
-        self.execution_count += other.count;
+        self.execution_count = self.execution_count.saturating_add(other.count);
         Ok(())
     }
 }
@@ -215,7 +366,10 @@ impl<'a> TryMerge<&'a Function> for Function {
                 other.name().clone()
             ));
         }
-        self.execution_count += *other.execution_count();
+        if self.end_line_number.is_none() {
+            self.end_line_number = other.end_line_number;
+        }
+        self.execution_count = self.execution_count.saturating_add(*other.execution_count());
         Ok(())
     }
 }
@@ -225,8 +379,8 @@ impl<'a> TryMerge<&'a Function> for Function {
 
 #[cfg(test)]
 mod tests {
-    use record:: { FunctionData };
-    use report::function:: { Function, Functions };
+    use record:: { FunctionName, FunctionData, FunctionLine, FunctionAlias };
+    use report::function:: { Function, Functions, FunctionFormat };
     use report::summary:: { Summary };
     use report::counter:: { FoundCounter, HitCounter };
     use merger::ops::*;
@@ -246,7 +400,7 @@ mod tests {
         let mut functions = Functions::new();
         functions.try_merge(&FunctionData { name: "main".to_string(), count: 1 }).unwrap();
 
-        let ref cloned_functions = functions.clone();
+        let cloned_functions = &functions.clone();
         functions.try_merge(cloned_functions).unwrap();
 
         assert_eq!( functions.get(&"main".to_string()), Some( &Function::new("main".to_string(), 0, 2)));
@@ -262,4 +416,79 @@ mod tests {
         assert_eq!( functions.hit_count(), 1 );
         assert_eq!( functions.found_count(), 2 );
     }
+
+    #[test]
+    fn add_function_aliases() {
+        let mut functions = Functions::new();
+        functions.try_merge(&FunctionLine { index: 0, start: 3, end: Some(8) }).unwrap();
+        functions.try_merge(&FunctionAlias { index: 0, count: 1, name: "foo".to_string() }).unwrap();
+        functions.try_merge(&FunctionAlias { index: 0, count: 0, name: "foo_alias".to_string() }).unwrap();
+        functions.try_merge(&FunctionAlias { index: 0, count: 2, name: "foo".to_string() }).unwrap();
+
+        let function = functions.get(&"foo".to_string()).unwrap();
+        assert_eq!( function, &Function::new("foo".to_string(), 3, 3) );
+        assert_eq!( function.execution_count(), &3 );
+        assert_eq!( function.end_line_number(), Some(&8) );
+
+        let aliases: Vec<&str> = functions.aliases(&"foo_alias".to_string())
+            .iter()
+            .map(|function| function.name().as_str())
+            .collect();
+        assert_eq!( aliases, vec!["foo", "foo_alias"] );
+    }
+
+    #[test]
+    fn add_function_alias_without_line() {
+        let mut functions = Functions::new();
+        let result = functions.try_merge(&FunctionAlias { index: 1, count: 1, name: "foo".to_string() });
+
+        assert_eq!( result, Err(FunctionError::UnknownIndex(1)) );
+    }
+
+    #[test]
+    fn display_as_alias_format() {
+        let mut functions = Functions::new();
+        functions.try_merge(&FunctionLine { index: 0, start: 10, end: None }).unwrap();
+        functions.try_merge(&FunctionLine { index: 1, start: 3, end: Some(8) }).unwrap();
+        functions.try_merge(&FunctionAlias { index: 0, count: 0, name: "bar".to_string() }).unwrap();
+        functions.try_merge(&FunctionAlias { index: 1, count: 1, name: "foo".to_string() }).unwrap();
+        functions.try_merge(&FunctionAlias { index: 1, count: 0, name: "foo_alias".to_string() }).unwrap();
+
+        let expected = concat!(
+            "FNL:0,3,8\n",
+            "FNA:0,1,foo\n",
+            "FNA:0,0,foo_alias\n",
+            "FNL:1,10\n",
+            "FNA:1,0,bar\n",
+            "FNF:2\n",
+            "FNH:1\n"
+        );
+        assert_eq!( functions.display_as(FunctionFormat::Alias).to_string(), expected );
+    }
+
+    #[test]
+    fn function_line_range() {
+        let mut functions = Functions::new();
+        functions.try_merge(&FunctionName { name: "main".to_string(), line: 3, end: Some(8) }).unwrap();
+        functions.try_merge(&FunctionName { name: "foo".to_string(), line: 10, end: None }).unwrap();
+
+        let main = functions.get(&"main".to_string()).unwrap();
+        assert_eq!( main.line_range(), Some(3..=8) );
+        assert!( main.contains_line(&8) );
+        assert!( !main.contains_line(&9) );
+
+        let foo = functions.get(&"foo".to_string()).unwrap();
+        assert_eq!( foo.line_range(), None );
+        assert!( !foo.contains_line(&10) );
+
+        let expected = concat!(
+            "FN:10,foo\n",
+            "FNDA:0,foo\n",
+            "FN:3,8,main\n",
+            "FNDA:0,main\n",
+            "FNF:2\n",
+            "FNH:0\n"
+        );
+        assert_eq!( functions.to_string(), expected );
+    }
 }
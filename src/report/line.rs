@@ -10,6 +10,7 @@ use std::io;
 use std::cmp::PartialEq;
 use std::collections::btree_map:: { BTreeMap };
 use std::convert::{ AsRef, From };
+use std::ops:: { RangeBounds };
 use std::fmt:: { Display, Formatter, Result };
 use record:: { LineData, RecordWrite };
 use report::summary:: { Summary };
@@ -18,6 +19,7 @@ use report::counter:: { Hit, HitFoundCounter, FoundCounter, HitCounter };
 use merger::ops:: { TryMerge, MergeResult, MergeLine, ChecksumError };
 
 #[derive(Debug, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Line {
     line_number: LineNumber,
     execution_count: ExecutionCount,
@@ -31,9 +33,9 @@ impl Line {
         checksum: Option<CheckSum>
     ) -> Self {
         Line {
-            line_number: line_number,
-            execution_count: execution_count,
-            checksum: checksum
+            line_number,
+            execution_count,
+            checksum
         }
     }
     pub fn line_number(&self) -> &LineNumber {
@@ -43,10 +45,7 @@ impl Line {
         &self.execution_count
     }
     pub fn checksum(&self) -> Option<&CheckSum> {
-        match self.checksum {
-            Some(ref v) => Some(v),
-            None => None
-        }
+        self.checksum.as_ref()
     }
     pub fn has_checkshum(&self) -> bool {
         self.checksum.is_some()
@@ -72,7 +71,7 @@ impl PartialEq for Line {
         if has_checkshum {
             return self.checksum.as_ref() == other.checksum();
         }
-        return &self.line_number == other.line_number();
+        &self.line_number == other.line_number()
     }
 }
 
@@ -91,7 +90,7 @@ impl<'a> TryMerge<&'a Line> for Line {
             }
             self.checksum = Some(o.clone());
         }
-        self.execution_count += *other.execution_count();
+        self.execution_count = self.execution_count.saturating_add(*other.execution_count());
         Ok(())
     }
 }
@@ -106,16 +105,42 @@ impl<'a> TryMerge<&'a LineData> for Line {
 
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct Lines {
     lines: BTreeMap<LineNumber, Line>
 }
 
+impl Default for Lines {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Lines {
     pub fn new() -> Self {
         Lines {
             lines: BTreeMap::new()
         }
     }
+    /// Returns the lines within the range of line numbers
+    pub fn range<R: RangeBounds<LineNumber>>(&self, range: R) -> Lines {
+        let lines = self.lines.range(range)
+            .map(|(line_number, line)| (*line_number, line.clone()))
+            .collect();
+        Lines { lines }
+    }
+    /// Set the checksums of the lines, the checksum is kept if the function returns None
+    pub fn set_checksums<F: FnMut(&LineNumber) -> Option<CheckSum>>(&mut self, mut checksum: F) {
+        for (line_number, line) in self.lines.iter_mut() {
+            if let Some(checksum) = checksum(line_number) {
+                line.checksum = Some(checksum);
+            }
+        }
+    }
+    /// Keep the lines which the predicate returns true for
+    pub fn retain<F: FnMut(&LineNumber) -> bool>(&mut self, mut predicate: F) {
+        self.lines.retain(|line_number, _| predicate(line_number));
+    }
 }
 
 impl AsRef<BTreeMap<LineNumber, Line>> for Lines {
@@ -174,12 +199,11 @@ impl<'a> TryMerge<&'a LineData> for Lines {
     type Err = ChecksumError;
 
     fn try_merge(&mut self, line_data: &'a LineData) -> MergeResult<Self::Err> {
-        if !self.lines.contains_key(&line_data.line) {
-            self.lines.insert(line_data.line, Line::from(line_data));
-            return Ok(());
+        if let Some(line) = self.lines.get_mut(&line_data.line) {
+            return line.try_merge(line_data);
         }
-        let line = self.lines.get_mut(&line_data.line).unwrap();
-        line.try_merge(line_data)
+        self.lines.insert(line_data.line, Line::from(line_data));
+        Ok(())
     }
 }
 
@@ -210,12 +234,21 @@ mod tests {
         let mut lines = Lines::new();
         lines.try_merge(&LineData { line: 1, count: 1, checksum: Some("abc".to_string()) }).unwrap();
 
-        let ref cloned_lines = lines.clone();
+        let cloned_lines = &lines.clone();
         lines.try_merge(cloned_lines).unwrap();
 
         assert_eq!( lines.get(&1), Some(&Line::new(1, 2, Some("abc".to_string()))) );
     }
 
+    #[test]
+    fn saturating_execution_count() {
+        let mut lines = Lines::new();
+        lines.try_merge(&LineData { line: 1, count: u64::MAX - 1, checksum: None }).unwrap();
+        lines.try_merge(&LineData { line: 1, count: 2, checksum: None }).unwrap();
+
+        assert_eq!( lines.get(&1), Some(&Line::new(1, u64::MAX, None)) );
+    }
+
     #[test]
     fn hit_count_and_found_count() {
         let mut lines = Lines::new();
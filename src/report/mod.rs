@@ -7,14 +7,23 @@
 // modified, or distributed except according to those terms.
 
 use std::fmt;
-use std::fs:: { OpenOptions, File as OutputFile };
 use std::convert::{ AsRef };
-use std::io:: { Result as IOResult };
+use std::io:: { Result as IOResult, Error as IOError, ErrorKind };
 use std::io::prelude::*;
-use std::path::Path;
+use std::path:: { Path, PathBuf };
+use std::collections::btree_map:: { BTreeMap };
 use report::summary:: { Summary };
 use report::file:: { File, Files };
+use report::test:: { Test, Tests };
+use report::attribute:: { TestName, SourceFile };
+use merger::ops:: { TryMerge, TestError };
+use report::function:: { FunctionFormat };
+use report::coverage:: { CoverageSummary };
+use report::diff:: { ReportDiff };
+#[cfg(feature = "filter")]
+use report::filter:: { FileFilter };
 use record:: { RecordWrite };
+use compression:: { TracefileWriter };
 
 pub mod attribute;
 pub mod summary;
@@ -24,18 +33,33 @@ pub mod line;
 pub mod function;
 pub mod test;
 pub mod counter;
+pub mod coverage;
+pub mod diff;
+#[cfg(feature = "filter")]
+pub mod filter;
+pub mod exclusion;
+#[cfg(feature = "checksum")]
+pub mod checksum;
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Report {
-    files: Files
+    files: Files,
+    #[cfg_attr(feature = "serde", serde(default))]
+    function_format: FunctionFormat
 }
 
 impl Report {
     pub fn new(files: Files) -> Self {
         Report {
-            files: files
+            files,
+            function_format: FunctionFormat::Legacy
         }
     }
+    /// Set the format of the function records written by `Display` and `save_as`
+    pub fn set_function_format(&mut self, format: FunctionFormat) {
+        self.function_format = format;
+    }
     pub fn get(&self, key: &str) -> Option<&File> {
         self.files.get(&key.to_string())
     }
@@ -45,15 +69,120 @@ impl Report {
     pub fn len(&self) -> usize {
         self.files.len()
     }
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+    /// Returns the report which has a single test with an empty name in each file.
+    /// The execution counts of the tests are summed, like `lcov --ignore-tests`.
+    pub fn flatten_tests(&self) -> Result<Report, TestError> {
+        let test_name = TestName::new();
+        let mut files = Files::new();
+
+        for (source_name, file) in self.files.iter() {
+            let mut tests = Tests::new();
+            tests.try_merge((&test_name, &file.combined()?))?;
+            files.try_merge((source_name, &File::new(tests)))?;
+        }
+        let mut report = Report::new(files);
+        report.set_function_format(self.function_format);
+        Ok(report)
+    }
+    /// Returns the report whose tests are updated in each source file.
+    /// The function is called once for each source file and returns the update of the tests, None keeps the file as it is.
+    pub(crate) fn map_tests<F, U>(&self, mut map: F) -> IOResult<Report>
+        where F: FnMut(&SourceFile) -> IOResult<Option<U>>, U: FnMut(&mut Test) {
+        let invalid_data = |err: TestError| IOError::new(ErrorKind::InvalidData, err.to_string());
+        let mut files = Files::new();
+
+        for (source_name, file) in self.files.iter() {
+            let mut update = match map(source_name)? {
+                Some(update) => update,
+                None => {
+                    files.try_merge((source_name, file)).map_err(invalid_data)?;
+                    continue;
+                }
+            };
+            let mut tests = Tests::new();
+
+            for (test_name, test) in file.tests().iter() {
+                let mut test = test.clone();
+                update(&mut test);
+                tests.try_merge((test_name, &test)).map_err(invalid_data)?;
+            }
+            files.try_merge((source_name, &File::new(tests))).map_err(invalid_data)?;
+        }
+
+        let mut report = Report::new(files);
+        report.set_function_format(self.function_format);
+        Ok(report)
+    }
+    /// Returns the coverage of all files
+    pub fn coverage_summary(&self) -> CoverageSummary {
+        self.files.iter().fold(CoverageSummary::default(), |summary, (_, file)| {
+            summary + file.coverage_summary()
+        })
+    }
+    /// Returns the coverage of the files for each directory.
+    /// The files in the subdirectories are not included in the coverage of the directory.
+    pub fn directory_summaries(&self) -> BTreeMap<String, CoverageSummary> {
+        let mut directories = BTreeMap::new();
+
+        for (source_name, file) in self.files.iter() {
+            let directory = Path::new(source_name).parent()
+                .map(|directory| directory.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            *directories.entry(directory).or_insert_with(CoverageSummary::default) += file.coverage_summary();
+        }
+        directories
+    }
+    /// Returns the report which has only the files kept by the filter
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lcov_parser:: { merge_files };
+    /// use lcov_parser::filter:: { FileFilter };
+    ///
+    /// let report = merge_files(&[ "tests/fixtures/merged/eq_checksum/fixture1.info" ]).unwrap();
+    /// let mut filter = FileFilter::new();
+    /// filter.exclude("/func*.c").unwrap();
+    ///
+    /// assert_eq!(report.filter(&filter).len(), 2);
+    /// ```
+    #[cfg(feature = "filter")]
+    pub fn filter(&self, filter: &FileFilter) -> Report {
+        let mut files = self.files.clone();
+        files.retain(|source_name| filter.is_match(source_name));
+
+        let mut report = Report::new(files);
+        report.set_function_format(self.function_format);
+        report
+    }
+    /// Returns the difference of the coverage from this report to the other report
+    pub fn diff(&self, other: &Report) -> ReportDiff {
+        ReportDiff::new(self, other)
+    }
+    /// Save the report, the file is compressed by the extension of the path (`.gz`, `.zst`)
+    /// with the `gzip` and `zstd` features.
     pub fn save_as<T: AsRef<Path>>(&self, path: T) -> IOResult<()> {
-        let mut output = OpenOptions::new().create(true).write(true).open(path)?;
-        self.write_records::<OutputFile>(&mut output)
+        let mut output = TracefileWriter::create(path)?;
+        self.write_records(&mut output)?;
+        output.finish()
+    }
+}
+
+/// Returns the path that the source file is read from.
+/// The path of the report is used if the root is not set, otherwise the path is joined to the root, including the absolute path.
+pub(crate) fn source_path(source_root: Option<&Path>, source_name: &str) -> PathBuf {
+    match source_root {
+        Some(root) => root.join(source_name.trim_start_matches('/')),
+        None => PathBuf::from(source_name)
     }
 }
 
 impl RecordWrite for Report {
     fn write_records<T: Write>(&self, output: &mut T) -> IOResult<()> {
-        writeln!(output, "{}", self)
+        write!(output, "{}", self)
     }
 }
 
@@ -63,7 +192,7 @@ impl fmt::Display for Report {
             for (test_name, test) in file.tests().iter() {
                 writeln!(f, "TN:{}", test_name)?;
                 writeln!(f, "SF:{}", source_name)?;
-                write!(f, "{}", test.functions())?;
+                write!(f, "{}", test.functions().display_as(self.function_format))?;
                 write!(f, "{}", test.branches())?;
                 write!(f, "{}", test.lines())?;
                 writeln!(f, "end_of_record")?;
@@ -79,9 +208,12 @@ mod tests {
 
     use self::tempdir::TempDir;
     use record::{ LineData, FunctionData, BranchData };
-    use report::test::{ Tests };
+    use report::test::{ Test, Tests };
+    use report::summary::{ Summary };
     use report::file;
     use report::{ Report };
+    use report::function::{ FunctionFormat };
+    use report::coverage::{ Coverage };
     use merger::ops:: { TryMerge };
     use std::fs::File;
     use std::io::*;
@@ -90,7 +222,7 @@ mod tests {
         let mut tests = Tests::new();
         let line_data = &LineData { line: 1, count: 1, checksum: None };
         let function_data = &FunctionData { name: "main".to_string(), count: 1 };
-        let branch_data = &BranchData { line: 1, block: 1, branch: 1, taken: 1 };
+        let branch_data = &BranchData { line: 1, block: 1, branch: 1, taken: Some(1) };
         let test_name = "test1".to_string();
 
         tests.try_merge((&test_name, line_data)).unwrap();
@@ -109,9 +241,68 @@ mod tests {
         let report = build_report();
         let tmp_dir = TempDir::new("report").expect("create temp dir");
         let file_path = tmp_dir.path().join("report.lcov");
-        let _ = report.save_as(file_path.clone()).unwrap();
+        report.save_as(file_path.clone()).unwrap();
+
+        assert!(file_path.as_path().exists());
+    }
+
+    #[test]
+    fn flatten_tests() {
+        let mut tests = Tests::new();
+        let test1 = "test1".to_string();
+        let test2 = "test2".to_string();
+
+        tests.try_merge((&test1, &LineData { line: 1, count: 1, checksum: None })).unwrap();
+        tests.try_merge((&test2, &LineData { line: 1, count: 2, checksum: None })).unwrap();
+        tests.try_merge((&test2, &LineData { line: 2, count: 0, checksum: None })).unwrap();
+
+        let mut files = file::Files::new();
+        files.try_merge((&"a.c".to_string(), &file::File::new(tests))).unwrap();
 
-        assert_eq!(file_path.as_path().exists(), true);
+        let report = Report::new(files).flatten_tests().unwrap();
+        assert_eq!(report.to_string(), concat!(
+            "TN:\n",
+            "SF:a.c\n",
+            "DA:1,3\n",
+            "DA:2,0\n",
+            "LF:2\n",
+            "LH:1\n",
+            "end_of_record\n"
+        ));
+    }
+
+    #[test]
+    fn map_tests() {
+        let mut report = build_report();
+        report.set_function_format(FunctionFormat::Alias);
+
+        let mapped = report.map_tests(|_| Ok(Some(|test: &mut Test| test.exclude_lines(|line_number| *line_number == 1)))).unwrap();
+        let test = mapped.get("a.c").unwrap().get_test(&"test1".to_string()).unwrap();
+        assert!(test.lines().get(&1).is_none());
+        assert_eq!(mapped.function_format, FunctionFormat::Alias);
+
+        let kept = report.map_tests(|_| Ok(None::<fn(&mut Test)>)).unwrap();
+        assert_eq!(kept.to_string(), report.to_string());
+    }
+
+    #[test]
+    fn coverage_summary() {
+        let mut files = file::Files::new();
+        let test_name = "test1".to_string();
+
+        for (source_name, count) in [ ("src/a.c", 1), ("src/b.c", 0), ("src/lib/c.c", 1) ].iter() {
+            let mut tests = Tests::new();
+            tests.try_merge((&test_name, &LineData { line: 1, count: *count, checksum: None })).unwrap();
+            files.try_merge((&source_name.to_string(), &file::File::new(tests))).unwrap();
+        }
+        let report = Report::new(files);
+
+        assert_eq!(report.coverage_summary().lines, Coverage::new(2, 3));
+
+        let directories = report.directory_summaries();
+        assert_eq!(directories.len(), 2);
+        assert_eq!(directories["src"].lines, Coverage::new(1, 2));
+        assert_eq!(directories["src/lib"].lines, Coverage::new(1, 1));
     }
 
     #[test]
@@ -126,4 +317,19 @@ mod tests {
         };
         assert_eq!(report.to_string(), readed_file_content);
     }
+
+    #[test]
+    fn display_with_function_alias() {
+        let mut report = build_report();
+        report.set_function_format(FunctionFormat::Alias);
+
+        let report_path = "tests/fixtures/report/report_with_function_alias.info";
+        let readed_file_content = {
+            let mut output = String::new();
+            let mut f = File::open(report_path).unwrap();
+            let _ = f.read_to_string(&mut output);
+            output
+        };
+        assert_eq!(report.to_string(), readed_file_content);
+    }
 }
@@ -8,31 +8,23 @@
 
 use std::default:: { Default };
 use std::collections::btree_map:: { BTreeMap };
-use record:: { LineData, FunctionName, FunctionData, BranchData };
+use record:: { LineData, FunctionName, FunctionData, FunctionLine, FunctionAlias, BranchData };
 use merger::ops:: { Merge, TryMerge, MergeResult, TestError, ChecksumError, FunctionError, BranchError };
-use report::attribute:: { TestName };
+use report::attribute:: { TestName, LineNumber, CheckSum, FunctionName as FunctionNameAttribute };
 use report::line:: { Lines };
-use report::function:: { Functions };
+use report::function:: { Function, Functions };
 use report::branch:: { Branches };
 use report::summary:: { Summary };
+use report::coverage:: { Coverage, CoverageSummary };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Test {
     lines: Lines,
     functions: Functions,
     branches: Branches
 }
 
-impl Default for Test {
-    fn default() -> Self {
-        Test {
-            lines: Lines::new(),
-            functions: Functions::new(),
-            branches: Branches::new()
-        }
-    }
-}
-
 impl Test {
     pub fn new() -> Self {
         Test {
@@ -50,12 +42,58 @@ impl Test {
     pub fn branches(&self) -> &Branches {
         &self.branches
     }
+    /// Set the checksums of the lines, the checksum is kept if the function returns None
+    pub fn set_checksums<F: FnMut(&LineNumber) -> Option<CheckSum>>(&mut self, checksum: F) {
+        self.lines.set_checksums(checksum);
+    }
+    /// Keep the lines which the predicate returns true for, the branches and the functions are kept
+    pub fn retain_lines<F: FnMut(&LineNumber) -> bool>(&mut self, predicate: F) {
+        self.lines.retain(predicate);
+    }
+    /// Remove the lines, and the branches and the functions starting on the lines
+    pub fn exclude_lines<F: Fn(&LineNumber) -> bool>(&mut self, excluded: F) {
+        self.lines.retain(|line_number| !excluded(line_number));
+        self.branches.retain(|line_number| !excluded(line_number));
+        self.functions.retain(|function| !excluded(function.line_number()));
+    }
+    /// Remove the branches on the lines
+    pub fn exclude_branches<F: Fn(&LineNumber) -> bool>(&mut self, excluded: F) {
+        self.branches.retain(|line_number| !excluded(line_number));
+    }
+    /// Returns the function containing the line.
+    /// If the functions are nested, the innermost function is returned.
+    /// Functions without an end line are not taken into account.
+    pub fn function_at(&self, line_number: &LineNumber) -> Option<&Function> {
+        self.functions.iter()
+            .map(|(_, function)| function)
+            .filter(|function| function.contains_line(line_number))
+            .max_by_key(|function| *function.line_number())
+    }
+    /// Returns the lines belonging to the function, if its line range is known
+    pub fn function_lines(&self, name: &FunctionNameAttribute) -> Option<Lines> {
+        let range = self.functions.get(name)?.line_range()?;
+        Some(self.lines.range(range))
+    }
+    /// Returns the branches belonging to the function, if its line range is known
+    pub fn function_branches(&self, name: &FunctionNameAttribute) -> Option<Branches> {
+        let range = self.functions.get(name)?.line_range()?;
+        Some(self.branches.range(range))
+    }
+    pub fn coverage_summary(&self) -> CoverageSummary {
+        CoverageSummary {
+            lines: Coverage::from(&self.lines),
+            functions: Coverage::from(&self.functions),
+            branches: Coverage::from(&self.branches)
+        }
+    }
 }
 
 
 impl_try_merge!(Test:lines, LineData, ChecksumError);
 impl_try_merge!(Test:functions, FunctionName, FunctionError);
 impl_try_merge!(Test:functions, FunctionData, FunctionError);
+impl_try_merge!(Test:functions, FunctionLine, FunctionError);
+impl_try_merge!(Test:functions, FunctionAlias, FunctionError);
 impl_try_merge!(Test:branches, BranchData, BranchError);
 
 impl<'a> TryMerge<&'a Test> for Test {
@@ -71,16 +109,26 @@ impl<'a> TryMerge<&'a Test> for Test {
 
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct Tests {
     tests: BTreeMap<TestName, Test>
 }
 
+impl Default for Tests {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Tests {
     pub fn new() -> Self {
         Tests {
             tests: BTreeMap::new()
         }
     }
+    pub(crate) fn get_mut(&mut self, test_name: &TestName) -> Option<&mut Test> {
+        self.tests.get_mut(test_name)
+    }
 }
 
 impl_summary!(Tests, tests<TestName, Test>);
@@ -131,6 +179,30 @@ impl<'a> TryMerge<(&'a String, &'a FunctionData)> for Tests {
     }
 }
 
+impl<'a> TryMerge<(&'a String, &'a FunctionLine)> for Tests {
+    type Err = FunctionError;
+
+    fn try_merge(&mut self, function_line: (&'a String, &'a FunctionLine)) -> MergeResult<Self::Err> {
+        if !self.tests.contains_key(function_line.0) {
+            self.tests.insert(function_line.0.clone(), Test::new());
+        }
+        let test = self.tests.get_mut(function_line.0).unwrap();
+        TryMerge::try_merge(test, function_line.1)
+    }
+}
+
+impl<'a> TryMerge<(&'a String, &'a FunctionAlias)> for Tests {
+    type Err = FunctionError;
+
+    fn try_merge(&mut self, function_alias: (&'a String, &'a FunctionAlias)) -> MergeResult<Self::Err> {
+        if !self.tests.contains_key(function_alias.0) {
+            self.tests.insert(function_alias.0.clone(), Test::new());
+        }
+        let test = self.tests.get_mut(function_alias.0).unwrap();
+        TryMerge::try_merge(test, function_alias.1)
+    }
+}
+
 impl<'a> TryMerge<(&'a String, &'a BranchData)> for Tests {
     type Err = BranchError;
 
@@ -143,6 +215,19 @@ impl<'a> TryMerge<(&'a String, &'a BranchData)> for Tests {
     }
 }
 
+impl<'a> TryMerge<(&'a String, &'a Test)> for Tests {
+    type Err = TestError;
+
+    fn try_merge(&mut self, test: (&'a String, &'a Test)) -> MergeResult<Self::Err> {
+        if !self.tests.contains_key(test.0) {
+            self.tests.insert(test.0.clone(), test.1.clone());
+            return Ok(());
+        }
+        let current = self.tests.get_mut(test.0).unwrap();
+        TryMerge::try_merge(current, test.1)
+    }
+}
+
 impl_try_merge_self_summary!(Tests:tests, TestError);
 
 
@@ -154,18 +239,19 @@ mod tests {
     use report::line:: { Line };
     use report::function:: { Function };
     use report::branch:: { BranchUnit, Branch, BranchBlocks };
-    use record:: { LineData, FunctionData, BranchData };
+    use record:: { LineData, FunctionName, FunctionData, BranchData };
+    use report::counter:: { FoundCounter, HitCounter };
 
     #[test]
     fn add_branch_data() {
         let test = {
             let mut test = Test::new();
-            test.try_merge( &BranchData { line: 1, block: 1, branch: 1, taken: 2 }).unwrap();
+            test.try_merge( &BranchData { line: 1, block: 1, branch: 1, taken: Some(2) }).unwrap();
             test
         };
         let branches = {
             let mut branches = BranchBlocks::new();
-            branches.try_merge( &BranchData { line: 1, block: 1, branch: 1, taken: 2 } ).unwrap();
+            branches.try_merge( &BranchData { line: 1, block: 1, branch: 1, taken: Some(2) } ).unwrap();
             branches
         };
         let lookup_branches = {
@@ -181,13 +267,13 @@ mod tests {
 
         test1.try_merge(&LineData { line: 1, count: 1, checksum: Some("xyz".to_string()) }).unwrap();
         test1.try_merge(&FunctionData { name: "main".to_string(), count: 1 }).unwrap();
-        test1.try_merge(&BranchData { line: 1, block: 1, branch: 1, taken: 1 }).unwrap();
+        test1.try_merge(&BranchData { line: 1, block: 1, branch: 1, taken: Some(1) }).unwrap();
 
         let test2 = {
             let mut test2 = Test::new();
             test2.try_merge(&LineData { line: 1, count: 1, checksum: Some("xyz".to_string()) }).unwrap();
             test2.try_merge(&FunctionData { name: "main".to_string(), count: 1 }).unwrap();
-            test2.try_merge(&BranchData { line: 1, block: 1, branch: 1, taken: 1 }).unwrap();
+            test2.try_merge(&BranchData { line: 1, block: 1, branch: 1, taken: Some(1) }).unwrap();
             test2
         };
         test1.try_merge(&test2).unwrap();
@@ -199,7 +285,7 @@ mod tests {
         assert_eq!( functions.get(&"main".to_string()), Some( &Function::new("main".to_string(), 0, 2)));
 
         let mut branches = BranchBlocks::new();
-        branches.try_merge(&BranchData { line: 1, block: 1, branch: 1, taken: 2 }).unwrap();
+        branches.try_merge(&BranchData { line: 1, block: 1, branch: 1, taken: Some(2) }).unwrap();
 
         let lookup_branches = {
             let branches = test1.branches();
@@ -213,7 +299,7 @@ mod tests {
         let mut tests = Tests::new();
         let line_data = &LineData { line: 1, count: 1, checksum: None };
         let function_data = &FunctionData { name: "main".to_string(), count: 1 };
-        let branch_data = &BranchData { line: 1, block: 1, branch: 1, taken: 1 };
+        let branch_data = &BranchData { line: 1, block: 1, branch: 1, taken: Some(1) };
         let test_name = "test1".to_string();
         let function_name = "main".to_string();
 
@@ -231,6 +317,47 @@ mod tests {
 
         assert_eq!( lines.get(&1), Some(&Line::new(1, 1, None)));
         assert_eq!( functions.get(&function_name), Some( &Function::new("main".to_string(), 0, 1)));
-        assert_eq!( branch_blocks.get(&BranchUnit::new(1, 1)), Some(&Branch::new(1, 1, 1, 1)));
+        assert_eq!( branch_blocks.get(&BranchUnit::new(1, 1)), Some(&Branch::new(1, 1, 1, Some(1))));
+    }
+
+    #[test]
+    fn function_coverage() {
+        let mut test = Test::new();
+        test.try_merge(&FunctionName { name: "main".to_string(), line: 1, end: Some(10) }).unwrap();
+        test.try_merge(&FunctionName { name: "foo".to_string(), line: 3, end: Some(5) }).unwrap();
+        test.try_merge(&FunctionName { name: "bar".to_string(), line: 12, end: None }).unwrap();
+        test.try_merge(&LineData { line: 2, count: 1, checksum: None }).unwrap();
+        test.try_merge(&LineData { line: 4, count: 0, checksum: None }).unwrap();
+        test.try_merge(&LineData { line: 11, count: 1, checksum: None }).unwrap();
+        test.try_merge(&BranchData { line: 4, block: 0, branch: 0, taken: Some(1) }).unwrap();
+
+        assert_eq!( test.function_at(&2).map(|f| f.name().as_str()), Some("main") );
+        assert_eq!( test.function_at(&4).map(|f| f.name().as_str()), Some("foo") );
+        assert_eq!( test.function_at(&11), None );
+        assert_eq!( test.function_at(&12), None );
+
+        let main_lines = test.function_lines(&"main".to_string()).unwrap();
+        assert_eq!( main_lines.found_count(), 2 );
+        assert_eq!( main_lines.hit_count(), 1 );
+
+        let foo_branches = test.function_branches(&"foo".to_string()).unwrap();
+        assert_eq!( foo_branches.found_count(), 1 );
+
+        assert!( test.function_lines(&"bar".to_string()).is_none() );
+        assert!( test.function_lines(&"baz".to_string()).is_none() );
+    }
+
+    #[test]
+    fn function_coverage_with_inverted_range() {
+        let mut test = Test::new();
+        test.try_merge(&FunctionName { name: "foo".to_string(), line: 10, end: Some(5) }).unwrap();
+        test.try_merge(&LineData { line: 7, count: 1, checksum: None }).unwrap();
+        test.try_merge(&BranchData { line: 7, block: 0, branch: 0, taken: Some(1) }).unwrap();
+
+        let foo = test.functions().get(&"foo".to_string()).unwrap();
+        assert_eq!( foo.line_range(), None );
+        assert_eq!( test.function_at(&7), None );
+        assert!( test.function_lines(&"foo".to_string()).is_none() );
+        assert!( test.function_branches(&"foo".to_string()).is_none() );
     }
 }
@@ -0,0 +1,56 @@
+extern crate lcov_parser;
+extern crate tempdir;
+
+use tempdir::TempDir;
+use lcov_parser:: { LCOVParser, LCOVRecord, merge_files };
+
+fn parse_fixture(path: &str) -> Vec<LCOVRecord> {
+    LCOVParser::open(path).unwrap().parse().unwrap()
+}
+
+#[test]
+#[cfg(feature = "gzip")]
+fn parse_gzip() {
+    let expected = parse_fixture("tests/fixtures/parser/report.lcov");
+    assert_eq!(parse_fixture("tests/fixtures/parser/report.lcov.gz"), expected);
+}
+
+#[test]
+#[cfg(feature = "zstd")]
+fn parse_zstd() {
+    let expected = parse_fixture("tests/fixtures/parser/report.lcov");
+    assert_eq!(parse_fixture("tests/fixtures/parser/report.lcov.zst"), expected);
+}
+
+#[test]
+#[cfg(all(feature = "gzip", feature = "zstd"))]
+fn merge_and_save_compressed() {
+    let report = merge_files(&[
+        "tests/fixtures/parser/report.lcov.gz",
+        "tests/fixtures/parser/report.lcov.zst"
+    ]).unwrap();
+
+    let tmp_dir = TempDir::new("compressed").expect("create temp dir");
+    for file_name in [ "merged.info.gz", "merged.info.zst" ].iter() {
+        let file_path = tmp_dir.path().join(file_name);
+        report.save_as(&file_path).unwrap();
+
+        let saved = merge_files(&[ &file_path ]).unwrap();
+        assert_eq!(saved.to_string(), report.to_string());
+    }
+}
+
+#[test]
+fn save_uncompressed() {
+    let report = merge_files(&[ "tests/fixtures/parser/report.lcov" ]).unwrap();
+
+    let tmp_dir = TempDir::new("compressed").expect("create temp dir");
+    let file_path = tmp_dir.path().join("merged.info");
+    report.save_as(&file_path).unwrap();
+
+    let records = parse_fixture(file_path.to_str().unwrap());
+    assert_eq!(records.last(), Some(&LCOVRecord::EndOfRecord));
+
+    let saved = merge_files(&[ &file_path ]).unwrap();
+    assert_eq!(saved.to_string(), report.to_string());
+}
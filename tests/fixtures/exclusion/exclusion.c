@@ -0,0 +1,16 @@
+#include <stdlib.h>
+
+int check(int a) {
+  if (a < 0) abort(); // LCOV_EXCL_LINE
+  return a;
+}
+/* LCOV_EXCL_START */
+static void unused(void) {
+  abort();
+}
+/* LCOV_EXCL_STOP */
+int main(void) {
+  if (check(1) == 2) return 1; // LCOV_EXCL_BR_LINE
+  if (check(2) == 2) return 0;
+  return 2; // NO_COVERAGE
+}
@@ -0,0 +1,37 @@
+extern crate lcov_parser;
+
+use std::fs:: { File };
+use std::io:: { Read };
+use lcov_parser:: { LCOVParser, LCOVRecord, FromFile, parse_report };
+
+fn read_fixture(path: &str) -> String {
+    let mut buffer = String::new();
+    File::open(path).unwrap().read_to_string(&mut buffer).unwrap();
+    buffer
+}
+
+fn parse_fixture(path: &str) -> Vec<LCOVRecord> {
+    LCOVParser::from_file(path).unwrap().parse().unwrap()
+}
+
+#[test]
+fn crlf_line_endings() {
+    let expected = parse_fixture("tests/fixtures/parser/report_with_checksum.lcov");
+    let records = parse_fixture("tests/fixtures/parser/report_crlf.lcov");
+    assert_eq!(records, expected);
+
+    let records = parse_report(&read_fixture("tests/fixtures/parser/report_crlf.lcov")).unwrap();
+    assert_eq!(records, expected);
+    assert_eq!(records[1], LCOVRecord::SourceFile("/path/to/example1.rs".to_string()));
+}
+
+#[test]
+fn without_trailing_newline() {
+    let expected = parse_fixture("tests/fixtures/parser/report.lcov");
+    let records = parse_fixture("tests/fixtures/parser/report_without_trailing_newline.lcov");
+    assert_eq!(records, expected);
+    assert_eq!(records.last(), Some(&LCOVRecord::EndOfRecord));
+
+    let records = parse_report(&read_fixture("tests/fixtures/parser/report_without_trailing_newline.lcov")).unwrap();
+    assert_eq!(records, expected);
+}
@@ -0,0 +1,75 @@
+extern crate lcov_parser;
+extern crate proptest;
+
+use proptest::prelude::*;
+use proptest::collection:: { vec };
+use lcov_parser:: { LCOVRecord, LineData, FunctionName, FunctionData, FunctionLine, FunctionAlias, BranchData, RecordWriter, RecordWrite, parse_report };
+
+fn name() -> BoxedStrategy<String> {
+    "[A-Za-z_][A-Za-z0-9_:.]{0,16}".boxed()
+}
+
+fn path() -> BoxedStrategy<String> {
+    "(/[A-Za-z0-9_.-]{1,8}){1,4}".boxed()
+}
+
+fn record() -> BoxedStrategy<LCOVRecord> {
+    prop_oneof![
+        proptest::option::of(name()).prop_map(LCOVRecord::TestName),
+        path().prop_map(LCOVRecord::SourceFile),
+        (any::<u32>(), any::<u64>(), proptest::option::of("[A-Za-z0-9+/]{22}"))
+            .prop_map(|(line, count, checksum)| LineData { line, count, checksum }.into()),
+        (any::<u32>(), proptest::option::of(any::<u32>()), name())
+            .prop_map(|(line, end, name)| FunctionName { name, line, end }.into()),
+        (any::<u64>(), name())
+            .prop_map(|(count, name)| FunctionData { name, count }.into()),
+        (any::<u32>(), any::<u32>(), proptest::option::of(any::<u32>()))
+            .prop_map(|(index, start, end)| FunctionLine { index, start, end }.into()),
+        (any::<u32>(), any::<u64>(), name())
+            .prop_map(|(index, count, name)| FunctionAlias { index, count, name }.into()),
+        any::<u32>().prop_map(LCOVRecord::FunctionsFound),
+        any::<u32>().prop_map(LCOVRecord::FunctionsHit),
+        any::<u32>().prop_map(LCOVRecord::LinesHit),
+        any::<u32>().prop_map(LCOVRecord::LinesFound),
+        (any::<u32>(), any::<u32>(), any::<u32>(), proptest::option::of(any::<u64>()))
+            .prop_map(|(line, block, branch, taken)| BranchData { line, block, branch, taken }.into()),
+        any::<u32>().prop_map(LCOVRecord::BranchesFound),
+        any::<u32>().prop_map(LCOVRecord::BranchesHit),
+        Just(LCOVRecord::EndOfRecord)
+    ].boxed()
+}
+
+proptest! {
+    #[test]
+    fn write_and_parse_records(records in vec(record(), 0..64)) {
+        let mut writer = RecordWriter::new(vec![]);
+        writer.write_all(records.iter()).unwrap();
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        prop_assert_eq!(parse_report(&output).unwrap(), records);
+    }
+}
+
+#[test]
+fn write_records() {
+    let records = parse_report(concat!(
+        "TN:\n",
+        "SF:/path/to/source.c\n",
+        "FN:1,5,main\n",
+        "FNDA:1,main\n",
+        "FNF:1\n",
+        "FNH:1\n",
+        "BRDA:2,0,0,-\n",
+        "BRF:1\n",
+        "BRH:0\n",
+        "DA:2,1,PF4Rz2r7RTliO9u6bZ7h6g\n",
+        "LF:1\n",
+        "LH:1\n",
+        "end_of_record\n"
+    )).unwrap();
+
+    let mut output = vec![];
+    records.write_records(&mut output).unwrap();
+
+    assert_eq!(parse_report(&String::from_utf8(output).unwrap()).unwrap(), records);
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "serde")]
+
+extern crate lcov_parser;
+extern crate serde_json;
+
+use lcov_parser:: { LCOVParser, LCOVRecord, FromFile, Report, merge_files };
+use lcov_parser::branch:: { BranchUnit };
+
+fn roundtrip(report: &Report) -> Report {
+    let json = serde_json::to_string(report).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn report_roundtrip() {
+    let report = merge_files(&[
+        "tests/fixtures/merged/eq_checksum/fixture1.info",
+        "tests/fixtures/merged/not_executed_branch/fixture.info"
+    ]).unwrap();
+    assert_eq!(roundtrip(&report).to_string(), report.to_string());
+}
+
+#[test]
+fn report_roundtrip_with_function_alias() {
+    let report = merge_files(&[ "tests/fixtures/merged/function_alias/fixture.info" ]).unwrap();
+    assert_eq!(roundtrip(&report).to_string(), report.to_string());
+}
+
+#[test]
+fn records_roundtrip() {
+    let records = LCOVParser::from_file("tests/fixtures/parser/report.lcov").unwrap().parse().unwrap();
+    let json = serde_json::to_string(&records).unwrap();
+    let restored: Vec<LCOVRecord> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, records);
+}
+
+#[test]
+fn report_schema() {
+    let report = merge_files(&[ "tests/fixtures/merged/not_executed_branch/fixture.info" ]).unwrap();
+    let json = serde_json::to_value(&report).unwrap();
+    let test = &json["files"]["/fixture.c"]["tests"]["example"];
+
+    assert_eq!(json["function_format"], "legacy");
+    assert_eq!(test["lines"]["4"]["execution_count"], 1);
+    assert_eq!(test["branches"]["4"]["0-0"]["execution_count"], 1);
+    assert_eq!(test["branches"]["6"]["0-1"]["execution_count"], serde_json::Value::Null);
+    assert_eq!(test["functions"]["main"]["line_number"], 1);
+}
+
+#[test]
+fn record_schema() {
+    let record = LCOVRecord::from("DA:1,2\n");
+    let json = serde_json::to_string(&record).unwrap();
+    assert_eq!(json, r#"{"type":"data","value":{"line":1,"count":2,"checksum":null}}"#);
+
+    let json = serde_json::to_string(&LCOVRecord::EndOfRecord).unwrap();
+    assert_eq!(json, r#"{"type":"end_of_record"}"#);
+}
+
+#[test]
+fn branch_unit_key() {
+    let json = serde_json::to_string(&BranchUnit::new(1, 2)).unwrap();
+    assert_eq!(json, r#""1-2""#);
+    assert_eq!(serde_json::from_str::<BranchUnit>(&json).unwrap(), BranchUnit::new(1, 2));
+    assert!(serde_json::from_str::<BranchUnit>(r#""1""#).is_err());
+}
@@ -43,18 +43,18 @@ fn without_checksum() {
 
     for record in records.iter() {
         match record {
-            &LCOVRecord::SourceFile(ref name) => {
+            LCOVRecord::SourceFile(name) => {
                 line_result = LineResult { executed: 0, unused: 0 };
                 file_result = FileResult { name: name.clone(), lines: LineResult { executed: 0, unused: 0 } };
             },
-            &LCOVRecord::Data(ref line) => {
+            LCOVRecord::Data(line) => {
                 if line.count >= 1 {
-                    line_result.executed = line_result.executed + 1;
+                    line_result.executed += 1;
                 } else {
-                    line_result.unused = line_result.unused + 1;
+                    line_result.unused += 1;
                 }
             },
-            &LCOVRecord::EndOfRecord => {
+            LCOVRecord::EndOfRecord => {
                 file_result.lines = line_result.clone();
                 result.files.push( file_result.clone() );
             },
@@ -65,11 +65,13 @@ fn without_checksum() {
     assert_eq!(records.len(), 13);
     assert_eq!(result.files.len(), 2);
 
-    let f1 = result.files.get(0).unwrap();
+    let f1 = result.files.first().unwrap();
+    assert_eq!(f1.name, "/path/to/example1.rs");
     assert_eq!(f1.lines.executed, 4);
     assert_eq!(f1.lines.unused, 0);
 
     let f2 = result.files.get(1).unwrap();
+    assert_eq!(f2.name, "/path/to/example2.rs");
     assert_eq!(f2.lines.executed, 0);
     assert_eq!(f2.lines.unused, 4);
 }